@@ -12,14 +12,17 @@ use ray_tracing::{
     },
     hittable::{
         BvhNode,
+        Hittable,
         HittableList,
+        LinearBvh,
         Sphere,
+        SplitStrategy,
     },
     material::Lambertian,
     texture::SolidColor,
 };
 
-fn gen_camera() -> Camera {
+fn gen_world() -> (HittableList, Stores) {
     let mut world = HittableList::default();
     let mut stores = Stores::default();
 
@@ -37,8 +40,11 @@ fn gen_camera() -> Camera {
         Lambertian::new(texture),
     ));
 
-    let bvh = BvhNode::from_list(world);
-    Builder::new(bvh, stores)
+    (world, stores)
+}
+
+fn gen_camera(world: impl Hittable + 'static, stores: Stores) -> Camera {
+    Builder::new(world, stores)
         .samples_per_pixel(10)
         .width(100)
         .quiet(true)
@@ -46,8 +52,22 @@ fn gen_camera() -> Camera {
 }
 
 pub fn criterion_benchmark(c: &mut Criterion) {
-    let camera = gen_camera();
-    c.bench_function("render", |b| b.iter(|| camera.render()));
+    // Both layouts get the same tree, so only the traversal differs
+    for (name, strategy) in [
+        ("median", SplitStrategy::Median),
+        ("sah", SplitStrategy::SAH),
+    ] {
+        let (world, stores) = gen_world();
+        let camera = gen_camera(BvhNode::from_list_with(world, strategy), stores);
+        c.bench_function(&format!("render {name}"), |b| b.iter(|| camera.render()));
+
+        let (world, stores) = gen_world();
+        let bvh = LinearBvh::from(BvhNode::from_list_with(world, strategy));
+        let camera = gen_camera(bvh, stores);
+        c.bench_function(&format!("render linear bvh {name}"), |b| {
+            b.iter(|| camera.render())
+        });
+    }
 }
 criterion_group!(benches, criterion_benchmark);
 criterion_main!(benches);
@@ -4,7 +4,10 @@ use std::{
     ops::Range,
 };
 
-use glam::Vec3A as Vec3;
+use glam::{
+    Affine3A,
+    Vec3A as Vec3,
+};
 use itertools::Itertools;
 
 use crate::{
@@ -53,6 +56,21 @@ impl Aabb {
             .unwrap()
     }
 
+    pub fn centroid(&self) -> Vec3 {
+        Vec3::new(
+            (self.x.start + self.x.end) * 0.5,
+            (self.y.start + self.y.end) * 0.5,
+            (self.z.start + self.z.end) * 0.5,
+        )
+    }
+
+    pub fn surface_area(&self) -> f32 {
+        let x = self.x.end - self.x.start;
+        let y = self.y.end - self.y.start;
+        let z = self.z.end - self.z.start;
+        2.0 * (x * y + y * z + z * x)
+    }
+
     pub fn longest_axis_comparator(
         &self,
     ) -> impl FnMut(&Box<dyn Hittable>, &Box<dyn Hittable>) -> Ordering {
@@ -104,6 +122,36 @@ impl Aabb {
         true
     }
 
+    /// Widens any axis thinner than `delta`, so planar shapes still get hit.
+    pub fn pad(&self, delta: f32) -> Self {
+        let pad_axis = |range: &Range<f32>| {
+            if range.end - range.start < delta {
+                range.expand(delta)
+            } else {
+                range.clone()
+            }
+        };
+        Self {
+            x: pad_axis(&self.x),
+            y: pad_axis(&self.y),
+            z: pad_axis(&self.z),
+        }
+    }
+
+    /// The box around this one after it has been transformed.
+    pub fn transform(&self, transform: &Affine3A) -> Self {
+        let corners = [self.x.start, self.x.end]
+            .into_iter()
+            .cartesian_product([self.y.start, self.y.end])
+            .cartesian_product([self.z.start, self.z.end])
+            .map(|((x, y), z)| transform.transform_point3a(Vec3::new(x, y, z)));
+        let (min, max) = corners.fold(
+            (Vec3::splat(f32::INFINITY), Vec3::splat(f32::NEG_INFINITY)),
+            |(min, max), corner| (min.min(corner), max.max(corner)),
+        );
+        Self::new(min, max)
+    }
+
     pub fn merge(&self, other: &Self) -> Self {
         Self {
             x: self.x.merge(&other.x),
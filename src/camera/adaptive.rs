@@ -0,0 +1,154 @@
+use std::hash::Hasher;
+
+use super::Film;
+use crate::fingerprint::Fingerprint;
+
+/// Settings for spending the render's samples where the noise is.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Adaptive {
+    /// Taken by every pixel even if that goes over the budget.
+    pub min_samples: usize,
+    pub max_samples: usize,
+    /// Pixels stop once the standard error of their mean brightness falls below this
+    /// fraction of it.
+    pub threshold: f32,
+}
+
+impl Default for Adaptive {
+    fn default() -> Self {
+        Self {
+            min_samples: 16,
+            max_samples: 1024,
+            threshold: 0.01,
+        }
+    }
+}
+
+impl Fingerprint for Adaptive {
+    fn fingerprint(&self, hasher: &mut dyn Hasher) {
+        self.min_samples.fingerprint(hasher);
+        self.max_samples.fingerprint(hasher);
+        self.threshold.fingerprint(hasher);
+    }
+}
+
+impl Adaptive {
+    /// # Errors
+    ///
+    /// Returns a message naming the first setting that is out of range.
+    pub fn validate(&self) -> Result<(), String> {
+        let (min, max) = (self.min_samples, self.max_samples);
+        if min == 0 || min > max {
+            return Err(format!(
+                "min_samples must be between 1 and max_samples, found {min} and {max}"
+            ));
+        }
+        let threshold = self.threshold;
+        if !(threshold.is_finite() && threshold > 0.0) {
+            return Err(format!("threshold must be positive, found {threshold}"));
+        }
+        Ok(())
+    }
+
+    /// `None` once the budget is spent or every pixel is done.
+    pub(super) fn next_targets(&self, film: &Film, budget: usize) -> Option<Vec<usize>> {
+        let mut targets = film.sample_counts();
+        if targets.iter().any(|&count| count < self.min_samples) {
+            for target in &mut targets {
+                *target = (*target).max(self.min_samples);
+            }
+            return Some(targets);
+        }
+
+        let remaining = budget.saturating_sub(film.total_samples());
+        let mut noisy: Vec<_> = (0..targets.len())
+            .filter(|&pixel| {
+                let stats = film.stats(pixel);
+                stats.count() < self.max_samples && stats.relative_error() >= self.threshold
+            })
+            .collect();
+        if remaining == 0 || noisy.is_empty() {
+            return None;
+        }
+
+        if remaining >= noisy.len() {
+            // Share out what is left, at most doubling each pixel so that its noise is
+            // judged again before it takes many more
+            let share = remaining / noisy.len();
+            for pixel in noisy {
+                let count = targets[pixel];
+                targets[pixel] += share.min(count).min(self.max_samples - count);
+            }
+        } else {
+            // Too few samples left for every noisy pixel, so the noisiest get them
+            noisy.sort_by(|&a, &b| {
+                let error = |pixel| film.stats(pixel).relative_error();
+                error(b).total_cmp(&error(a))
+            });
+            for &pixel in &noisy[..remaining] {
+                targets[pixel] += 1;
+            }
+        }
+        Some(targets)
+    }
+}
+
+/// Nearly black pixels are judged as if they were this bright, so they don't take
+/// every sample chasing noise that can't be seen.
+const MIN_MEAN: f32 = 0.01;
+
+/// Welford's running mean and variance of a pixel's brightness.
+#[derive(Debug, Default, Clone, Copy)]
+pub(super) struct PixelStats {
+    pub(super) count: usize,
+    pub(super) mean: f32,
+    /// The sum of squared differences from the mean.
+    pub(super) m2: f32,
+}
+
+impl PixelStats {
+    pub(super) fn count(&self) -> usize {
+        self.count
+    }
+
+    pub(super) fn add(&mut self, value: f32) {
+        self.count += 1;
+        let delta = value - self.mean;
+        self.mean += delta / self.count as f32;
+        self.m2 += delta * (value - self.mean);
+    }
+
+    /// The standard error of the mean relative to the mean.
+    pub(super) fn relative_error(&self) -> f32 {
+        if self.count < 2 {
+            return f32::INFINITY;
+        }
+        let variance = self.m2 / (self.count - 1) as f32;
+        (variance / self.count as f32).sqrt() / self.mean.abs().max(MIN_MEAN)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate() {
+        assert!(Adaptive::default().validate().is_ok());
+        for (min_samples, max_samples) in [(0, 16), (32, 16)] {
+            let adaptive = Adaptive {
+                min_samples,
+                max_samples,
+                ..Adaptive::default()
+            };
+            assert!(adaptive.validate().is_err());
+        }
+        for threshold in [0.0, -0.1, f32::NAN, f32::INFINITY] {
+            let adaptive = Adaptive {
+                threshold,
+                ..Adaptive::default()
+            };
+            assert!(adaptive.validate().is_err());
+        }
+    }
+}
@@ -0,0 +1,82 @@
+use std::hash::Hasher;
+
+use super::Stores;
+use crate::{
+    color::{
+        Color,
+        LIGHT_BLUE,
+        WHITE,
+    },
+    environment::EnvironmentMap,
+    fingerprint::Fingerprint,
+    hittable::sphere_uv,
+    texture::TextureHandle,
+    timed_ray::TimedRay,
+};
+
+/// What a ray sees when it escapes the world.
+#[derive(Debug)]
+pub enum Background {
+    Solid(Color),
+    /// Blends from `bottom` when looking straight down to `top` when looking straight up.
+    Gradient {
+        bottom: Color,
+        top: Color,
+    },
+    /// Looks up the texture with the spherical coordinates of the ray direction, so an
+    /// equirectangular image wraps around the scene.
+    Texture(TextureHandle),
+    /// An HDR environment map that can also be importance sampled as a light.
+    Environment(EnvironmentMap),
+}
+
+impl Default for Background {
+    fn default() -> Self {
+        Self::Gradient {
+            bottom: WHITE,
+            top: LIGHT_BLUE,
+        }
+    }
+}
+
+impl Fingerprint for Background {
+    fn fingerprint(&self, hasher: &mut dyn Hasher) {
+        match self {
+            Self::Solid(color) => {
+                "Solid".fingerprint(hasher);
+                color.fingerprint(hasher);
+            }
+            Self::Gradient { bottom, top } => {
+                "Gradient".fingerprint(hasher);
+                bottom.fingerprint(hasher);
+                top.fingerprint(hasher);
+            }
+            Self::Texture(texture) => {
+                "Texture".fingerprint(hasher);
+                texture.fingerprint(hasher);
+            }
+            Self::Environment(environment) => {
+                "Environment".fingerprint(hasher);
+                environment.fingerprint(hasher);
+            }
+        }
+    }
+}
+
+impl Background {
+    pub fn color(&self, r: &TimedRay, stores: &Stores) -> Color {
+        let unit_direction = r.direction.normalize();
+        match self {
+            Self::Solid(color) => *color,
+            Self::Gradient { bottom, top } => {
+                let t = 0.5 * (unit_direction.y + 1.0);
+                bottom.lerp(top, t)
+            }
+            Self::Texture(texture) => stores
+                .textures
+                .get(*texture)
+                .value(sphere_uv(unit_direction), unit_direction),
+            Self::Environment(environment) => environment.value(unit_direction),
+        }
+    }
+}
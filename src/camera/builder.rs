@@ -1,12 +1,26 @@
-use std::f32::consts::PI;
+use std::{
+    f32::consts::PI,
+    path::PathBuf,
+    time::Duration,
+};
 
 use glam::Vec3A as Vec3;
 
 use super::{
+    Adaptive,
+    Background,
     Camera,
+    Filter,
+    Independent,
+    Integrator,
+    PathTracer,
+    Sampler,
     Stores,
 };
-use crate::hittable::Hittable;
+use crate::{
+    hittable::Hittable,
+    tonemap::ToneMap,
+};
 
 pub struct Builder {
     world: Box<dyn Hittable>,
@@ -22,6 +36,16 @@ pub struct Builder {
     defocus_angle: f32,
     focus_dist: f32,
     quiet: bool,
+    background: Background,
+    russian_roulette: Option<usize>,
+    integrator: Box<dyn Integrator>,
+    tone_map: ToneMap,
+    seed: u64,
+    sampler: Box<dyn Sampler>,
+    filter: Filter,
+    adaptive: Option<Adaptive>,
+    checkpoint: Option<PathBuf>,
+    checkpoint_interval: Duration,
 }
 
 impl Builder {
@@ -40,6 +64,16 @@ impl Builder {
             defocus_angle: 0.0,
             focus_dist: 10.0,
             quiet: false,
+            background: Background::default(),
+            russian_roulette: None,
+            integrator: Box::new(PathTracer),
+            tone_map: ToneMap::default(),
+            seed: fastrand::u64(..),
+            sampler: Box::new(Independent),
+            filter: Filter::default(),
+            adaptive: None,
+            checkpoint: None,
+            checkpoint_interval: Duration::from_secs(300),
         }
     }
 
@@ -105,6 +139,84 @@ impl Builder {
         self
     }
 
+    pub fn background(mut self, background: Background) -> Self {
+        self.background = background;
+        self
+    }
+
+    /// Randomly ends paths after `min_depth` bounces, more likely the less light they
+    /// can still carry.
+    pub fn russian_roulette(mut self, min_depth: Option<usize>) -> Self {
+        self.russian_roulette = min_depth;
+        self
+    }
+
+    pub fn integrator(mut self, integrator: impl Integrator + 'static) -> Self {
+        self.integrator = Box::new(integrator);
+        self
+    }
+
+    pub fn tone_map(mut self, tone_map: ToneMap) -> Self {
+        self.tone_map = tone_map;
+        self
+    }
+
+    /// Without one, a random seed is used.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    pub fn sampler(mut self, sampler: impl Sampler + 'static) -> Self {
+        self.sampler = Box::new(sampler);
+        self
+    }
+
+    /// # Panics
+    ///
+    /// Panics if [`Filter::validate`] rejects `filter`.
+    pub fn filter(mut self, filter: Filter) -> Self {
+        if let Err(error) = filter.validate() {
+            panic!("invalid filter: {error}");
+        }
+        self.filter = filter;
+        self
+    }
+
+    /// Spends `samples_per_pixel` on average where the noise is.
+    ///
+    /// # Panics
+    ///
+    /// Panics if [`Adaptive::validate`] rejects `adaptive`.
+    pub fn adaptive(mut self, adaptive: Option<Adaptive>) -> Self {
+        if let Some(Err(error)) = adaptive.map(|adaptive| adaptive.validate()) {
+            panic!("invalid adaptive sampling: {error}");
+        }
+        self.adaptive = adaptive;
+        self
+    }
+
+    /// Saved after every pass and every `checkpoint_interval`, for
+    /// [`Camera::resume`].
+    pub fn checkpoint(mut self, path: Option<PathBuf>) -> Self {
+        self.checkpoint = path;
+        self
+    }
+
+    pub fn checkpoint_interval(mut self, interval: Duration) -> Self {
+        self.checkpoint_interval = interval;
+        self
+    }
+
+    /// Whether `vup` points along the view direction, which leaves the camera's roll
+    /// undefined.
+    pub(crate) fn is_vup_along_view(&self) -> bool {
+        (self.look_at - self.look_from)
+            .cross(self.vup)
+            .length_squared()
+            == 0.0
+    }
+
     pub fn build(self) -> Camera {
         let camera_center = self.look_from;
 
@@ -149,6 +261,17 @@ impl Builder {
             defocus_dist_u,
             defocus_dist_v,
             quiet: self.quiet,
+            background: self.background,
+            russian_roulette: self.russian_roulette,
+            integrator: self.integrator,
+            look_distance: (self.look_from - self.look_at).length(),
+            tone_map: self.tone_map,
+            seed: self.seed,
+            sampler: self.sampler,
+            filter: self.filter,
+            adaptive: self.adaptive,
+            checkpoint: self.checkpoint,
+            checkpoint_interval: self.checkpoint_interval,
         }
     }
 }
@@ -0,0 +1,189 @@
+use std::{
+    error::Error,
+    fmt,
+    fs::{
+        self,
+        File,
+    },
+    hash::Hasher,
+    io::{
+        self,
+        BufReader,
+        BufWriter,
+        Read,
+        Write,
+    },
+    path::{
+        Path,
+        PathBuf,
+    },
+};
+
+use super::Film;
+
+/// Bumped whenever the layout changes.
+const MAGIC: &[u8; 8] = b"RTCKPT03";
+
+/// A render in progress, saved so that it can be carried on with later.
+pub struct Checkpoint {
+    pub(super) seed: u64,
+    pub(super) scene_hash: u64,
+    pub(super) film: Film,
+}
+
+impl Checkpoint {
+    /// The seed the render used, which the scene must be built with again to resume.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// # Errors
+    ///
+    /// Returns an error if the file can't be read or isn't a checkpoint.
+    pub fn load(path: &Path) -> Result<Self, CheckpointError> {
+        let io_error = |source| CheckpointError::Io {
+            path: path.to_path_buf(),
+            source,
+        };
+        let invalid = || CheckpointError::Invalid {
+            path: path.to_path_buf(),
+        };
+        let mut reader = BufReader::new(File::open(path).map_err(io_error)?);
+        let file_len = reader.get_ref().metadata().map_err(io_error)?.len();
+        let mut magic = [0; 8];
+        reader.read_exact(&mut magic).map_err(io_error)?;
+        if &magic != MAGIC {
+            return Err(invalid());
+        }
+
+        let mut header = [0; 4];
+        for value in &mut header {
+            let mut bytes = [0; 8];
+            reader.read_exact(&mut bytes).map_err(io_error)?;
+            *value = u64::from_le_bytes(bytes);
+        }
+        let [seed, scene_hash, width, height] = header;
+        // Checked before the film is allocated, so a corrupt header can't ask for more
+        // memory than the file could fill
+        let expected_len = Film::written_len(width, height)
+            .and_then(|len| len.checked_add((MAGIC.len() + header.len() * 8) as u64));
+        if expected_len != Some(file_len) {
+            return Err(invalid());
+        }
+        let film = Film::read(&mut reader, width as usize, height as usize).map_err(io_error)?;
+        if reader.read(&mut [0]).map_err(io_error)? != 0 {
+            return Err(invalid());
+        }
+        Ok(Self {
+            seed,
+            scene_hash,
+            film,
+        })
+    }
+
+    /// Writes next to `path` first and then moves the file over it, so an interrupted
+    /// save leaves the last checkpoint intact.
+    pub(super) fn save(&self, path: &Path) -> io::Result<()> {
+        let partial = path.with_extension("partial");
+        let mut writer = BufWriter::new(File::create(&partial)?);
+        writer.write_all(MAGIC)?;
+        let width = self.film.width() as u64;
+        let height = self.film.height() as u64;
+        for value in [self.seed, self.scene_hash, width, height] {
+            writer.write_all(&value.to_le_bytes())?;
+        }
+        self.film.write(&mut writer)?;
+        writer
+            .into_inner()
+            .map_err(io::IntoInnerError::into_error)?
+            .sync_all()?;
+        fs::rename(partial, path)
+    }
+}
+
+#[derive(Debug)]
+pub enum CheckpointError {
+    Io { path: PathBuf, source: io::Error },
+    Invalid { path: PathBuf },
+    Mismatch,
+}
+
+impl fmt::Display for CheckpointError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io { path, source } => write!(f, "{}: {source}", path.display()),
+            Self::Invalid { path } => write!(f, "{}: not a checkpoint", path.display()),
+            Self::Mismatch => write!(
+                f,
+                "the checkpoint is of a different scene, camera settings or seed"
+            ),
+        }
+    }
+}
+
+impl Error for CheckpointError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io { source, .. } => Some(source),
+            Self::Invalid { .. } | Self::Mismatch => None,
+        }
+    }
+}
+
+/// FNV-1a over everything written to it, which unlike `DefaultHasher` stays the same
+/// from one build to the next.
+pub(super) struct Fnv(u64);
+
+impl Default for Fnv {
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for Fnv {
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= u64::from(byte);
+            self.0 = self.0.wrapping_mul(0x100_0000_01b3);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::process;
+
+    use super::*;
+
+    #[test]
+    fn test_load_rejects_sizes_that_disagree_with_the_header() {
+        let name = format!("ray-tracing-checkpoint-load-test-{}.ckpt", process::id());
+        let path = std::env::temp_dir().join(name);
+        let checkpoint = Checkpoint {
+            seed: 1,
+            scene_hash: 2,
+            film: Film::new(3, 2),
+        };
+        checkpoint.save(&path).unwrap();
+        let bytes = fs::read(&path).unwrap();
+        assert!(Checkpoint::load(&path).is_ok());
+
+        let mut huge = bytes.clone();
+        huge[24..32].copy_from_slice(&u64::MAX.to_le_bytes());
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        let truncated = &bytes[..bytes.len() - 1];
+        for contents in [&huge[..], &trailing, truncated] {
+            fs::write(&path, contents).unwrap();
+            assert!(matches!(
+                Checkpoint::load(&path),
+                Err(CheckpointError::Invalid { .. })
+            ));
+        }
+        fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,288 @@
+use std::io::{
+    self,
+    Read,
+    Write,
+};
+
+use glam::{
+    Vec2,
+    Vec3A as Vec3,
+};
+
+use super::{
+    adaptive::PixelStats,
+    Filter,
+};
+use crate::color::{
+    Color,
+    BLACK,
+};
+
+/// What the passes of a render add up to, and how many samples each pixel should
+/// have by the end of the current one.
+pub struct Film {
+    width: usize,
+    height: usize,
+    sums: Vec<Vec3>,
+    weights: Vec<f32>,
+    /// Unweighted sums of each pixel's own samples, for pixels whose weights cancel
+    /// out.
+    unfiltered: Vec<Vec3>,
+    stats: Vec<PixelStats>,
+    targets: Vec<usize>,
+}
+
+impl Film {
+    pub(super) fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            sums: vec![Vec3::ZERO; width * height],
+            weights: vec![0.0; width * height],
+            unfiltered: vec![Vec3::ZERO; width * height],
+            stats: vec![PixelStats::default(); width * height],
+            targets: vec![0; width * height],
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub(super) fn min_sample_count(&self) -> usize {
+        self.stats.iter().map(PixelStats::count).min().unwrap_or(0)
+    }
+
+    pub(super) fn total_samples(&self) -> usize {
+        self.stats.iter().map(PixelStats::count).sum()
+    }
+
+    pub(super) fn is_pass_done(&self) -> bool {
+        self.stats
+            .iter()
+            .zip(&self.targets)
+            .all(|(stats, &target)| stats.count() >= target)
+    }
+
+    pub(super) fn target(&self, pixel: usize) -> usize {
+        self.targets[pixel]
+    }
+
+    pub(super) fn set_targets(&mut self, targets: Vec<usize>) {
+        assert_eq!(targets.len(), self.targets.len());
+        self.targets = targets;
+    }
+
+    /// How many samples each pixel took, row by row.
+    pub fn sample_counts(&self) -> Vec<usize> {
+        self.stats.iter().map(PixelStats::count).collect()
+    }
+
+    pub(super) fn stats(&self, pixel: usize) -> PixelStats {
+        self.stats[pixel]
+    }
+
+    /// Scaled from the fewest samples to the most.
+    pub fn sample_heatmap(&self) -> Vec<Color> {
+        let counts = self.sample_counts();
+        let min = counts.iter().copied().min().unwrap_or(0);
+        let max = counts.iter().copied().max().unwrap_or(0);
+        counts
+            .iter()
+            .map(|&count| Color::heat((count - min) as f32 / (max - min).max(1) as f32))
+            .collect()
+    }
+
+    pub(super) fn add(&mut self, splat: &Splat) {
+        let center = splat.y * self.width + splat.x;
+        self.stats[center] = splat.stats;
+        self.unfiltered[center] += splat.unfiltered;
+        let side = splat.side();
+        for j in 0..side {
+            for i in 0..side {
+                let (Some(x), Some(y)) = (
+                    (splat.x + i).checked_sub(splat.reach),
+                    (splat.y + j).checked_sub(splat.reach),
+                ) else {
+                    continue;
+                };
+                if x >= self.width || y >= self.height {
+                    continue;
+                }
+                let pixel = y * self.width + x;
+                self.sums[pixel] += splat.sums[j * side + i];
+                self.weights[pixel] += splat.weights[j * side + i];
+            }
+        }
+    }
+
+    /// Little-endian, so checkpoints move between machines.
+    pub(super) fn write(&self, writer: &mut impl Write) -> io::Result<()> {
+        for pixel in 0..self.sums.len() {
+            let (sum, weight) = (self.sums[pixel], self.weights[pixel]);
+            let (unfiltered, stats) = (self.unfiltered[pixel], self.stats[pixel]);
+            let values = [
+                sum.x,
+                sum.y,
+                sum.z,
+                weight,
+                unfiltered.x,
+                unfiltered.y,
+                unfiltered.z,
+                stats.mean,
+                stats.m2,
+            ];
+            for value in values {
+                writer.write_all(&value.to_le_bytes())?;
+            }
+            for count in [stats.count, self.targets[pixel]] {
+                writer.write_all(&(count as u64).to_le_bytes())?;
+            }
+        }
+        Ok(())
+    }
+
+    pub(super) fn written_len(width: u64, height: u64) -> Option<u64> {
+        // Nine `f32`s and two `u64`s per pixel
+        width.checked_mul(height)?.checked_mul(9 * 4 + 2 * 8)
+    }
+
+    pub(super) fn read(reader: &mut impl Read, width: usize, height: usize) -> io::Result<Self> {
+        let mut film = Self::new(width, height);
+        for pixel in 0..width * height {
+            let mut values = [0.0; 9];
+            for value in &mut values {
+                let mut bytes = [0; 4];
+                reader.read_exact(&mut bytes)?;
+                *value = f32::from_le_bytes(bytes);
+            }
+            let mut counts = [0; 2];
+            for count in &mut counts {
+                let mut bytes = [0; 8];
+                reader.read_exact(&mut bytes)?;
+                *count = u64::from_le_bytes(bytes) as usize;
+            }
+
+            let [x, y, z, weight, unfiltered_x, unfiltered_y, unfiltered_z, mean, m2] = values;
+            film.sums[pixel] = Vec3::new(x, y, z);
+            film.weights[pixel] = weight;
+            film.unfiltered[pixel] = Vec3::new(unfiltered_x, unfiltered_y, unfiltered_z);
+            film.stats[pixel] = PixelStats {
+                count: counts[0],
+                mean,
+                m2,
+            };
+            film.targets[pixel] = counts[1];
+        }
+        Ok(film)
+    }
+
+    /// Negative filter lobes can push a pixel below zero, which is clamped away, or
+    /// cancel out its weights, which falls back to the plain average of its own
+    /// samples.
+    pub fn pixels(&self) -> Vec<Color> {
+        (0..self.sums.len())
+            .map(|pixel| {
+                let weight = self.weights[pixel];
+                let count = self.stats[pixel].count();
+                let color = if weight > 0.0 {
+                    self.sums[pixel] / weight
+                } else if count > 0 {
+                    self.unfiltered[pixel] / count as f32
+                } else {
+                    return BLACK;
+                };
+                Color(color.max(Vec3::ZERO))
+            })
+            .collect()
+    }
+}
+
+/// The samples of one pixel spread over it and the pixels around it, kept apart until
+/// they go on the film so that pixels can be rendered in parallel.
+pub(super) struct Splat {
+    x: usize,
+    y: usize,
+    /// How many pixels the filter reaches past the pixel on each side.
+    reach: usize,
+    sums: Vec<Vec3>,
+    weights: Vec<f32>,
+    unfiltered: Vec3,
+    /// The pixel's statistics including these samples.
+    pub(super) stats: PixelStats,
+}
+
+impl Splat {
+    pub(super) fn new(x: usize, y: usize, filter: &Filter, stats: PixelStats) -> Self {
+        // Samples lie within half a pixel of the center
+        let reach = (filter.radius() - 0.5).ceil().max(0.0) as usize;
+        let size = (2 * reach + 1) * (2 * reach + 1);
+        Self {
+            x,
+            y,
+            reach,
+            sums: vec![Vec3::ZERO; size],
+            weights: vec![0.0; size],
+            unfiltered: Vec3::ZERO,
+            stats,
+        }
+    }
+
+    fn side(&self) -> usize {
+        2 * self.reach + 1
+    }
+
+    /// Adds a sample taken `offset` from the pixel's center.
+    pub(super) fn add(&mut self, filter: &Filter, offset: Vec2, color: Color) {
+        self.stats.add(color.luminance());
+        self.unfiltered += color.0;
+        let side = self.side();
+        for j in 0..side {
+            for i in 0..side {
+                let neighbor = Vec2::new(i as f32, j as f32) - self.reach as f32;
+                let weight = filter.weight(offset - neighbor);
+                self.sums[j * side + i] += color.0 * weight;
+                self.weights[j * side + i] += weight;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::WHITE;
+
+    #[test]
+    fn test_splats_are_clipped_to_the_image() {
+        // Every neighbor of a sample at the pixel's center gets a weight of one
+        let filter = Filter::Box { radius: 1.5 };
+        let mut film = Film::new(3, 2);
+        for (x, y) in [(0, 0), (2, 1)] {
+            let mut splat = Splat::new(x, y, &filter, PixelStats::default());
+            splat.add(&filter, Vec2::ZERO, WHITE);
+            film.add(&splat);
+        }
+
+        assert_eq!(film.weights, [1.0, 2.0, 1.0, 1.0, 2.0, 1.0]);
+        assert_eq!(film.sample_counts(), [1, 0, 0, 0, 0, 1]);
+        assert!(film.pixels().iter().all(|pixel| pixel.0 == WHITE.0));
+    }
+
+    #[test]
+    fn test_cancelled_weights_fall_back_to_the_plain_average() {
+        let filter = Filter::Lanczos { radius: 2.0 };
+        let mut film = Film::new(1, 1);
+        let mut splat = Splat::new(0, 0, &filter, PixelStats::default());
+        // Lands in the negative lobe
+        splat.add(&filter, Vec2::new(0.0, 1.4), Color::new(0.5, 0.5, 0.5));
+        film.add(&splat);
+
+        assert!(film.weights[0] < 0.0);
+        assert_eq!(film.pixels()[0].0, Vec3::splat(0.5));
+    }
+}
@@ -0,0 +1,184 @@
+use std::{
+    f32::consts::PI,
+    hash::Hasher,
+};
+
+use glam::Vec2;
+
+use crate::fingerprint::Fingerprint;
+
+/// How much a sample counts towards a pixel, depending on how far it is from the
+/// pixel's center. Distances and radii are in pixels, and samples further than the
+/// radius don't count.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Filter {
+    /// Every sample within the radius counts the same. A radius of half a pixel only
+    /// averages the pixel's own samples.
+    Box { radius: f32 },
+    /// Falls off linearly from the center.
+    Tent { radius: f32 },
+    /// A Gaussian with standard deviation `sigma`, shifted down to reach zero at the
+    /// radius.
+    Gaussian { radius: f32, sigma: f32 },
+    /// The Mitchell–Netravali cubic stretched over the radius. `b = c = 1 / 3` is the
+    /// usual choice, and the negative lobes sharpen edges.
+    Mitchell { radius: f32, b: f32, c: f32 },
+    /// A sinc windowed by a wider sinc, with as many lobes as the radius.
+    Lanczos { radius: f32 },
+}
+
+impl Default for Filter {
+    fn default() -> Self {
+        Self::Box { radius: 0.5 }
+    }
+}
+
+impl Fingerprint for Filter {
+    fn fingerprint(&self, hasher: &mut dyn Hasher) {
+        let (name, parameters) = match *self {
+            Self::Box { radius } => ("Box", [radius, 0.0, 0.0]),
+            Self::Tent { radius } => ("Tent", [radius, 0.0, 0.0]),
+            Self::Gaussian { radius, sigma } => ("Gaussian", [radius, sigma, 0.0]),
+            Self::Mitchell { radius, b, c } => ("Mitchell", [radius, b, c]),
+            Self::Lanczos { radius } => ("Lanczos", [radius, 0.0, 0.0]),
+        };
+        name.fingerprint(hasher);
+        parameters.fingerprint(hasher);
+    }
+}
+
+impl Filter {
+    pub fn radius(&self) -> f32 {
+        match *self {
+            Self::Box { radius }
+            | Self::Tent { radius }
+            | Self::Gaussian { radius, .. }
+            | Self::Mitchell { radius, .. }
+            | Self::Lanczos { radius } => radius,
+        }
+    }
+
+    /// Checks that the radius is positive and the shape parameters give finite
+    /// weights.
+    ///
+    /// # Errors
+    ///
+    /// Returns a message naming the first parameter that is out of range.
+    pub fn validate(&self) -> Result<(), String> {
+        let radius = self.radius();
+        if !(radius.is_finite() && radius > 0.0) {
+            return Err(format!("radius must be positive, found {radius}"));
+        }
+        match *self {
+            Self::Gaussian { sigma, .. } if !(sigma.is_finite() && sigma > 0.0) => {
+                Err(format!("sigma must be positive, found {sigma}"))
+            }
+            Self::Mitchell { b, c, .. } if !(b.is_finite() && c.is_finite()) => {
+                Err(format!("b and c must be finite, found {b} and {c}"))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// The weight of a sample `offset` from the pixel's center.
+    pub fn weight(&self, offset: Vec2) -> f32 {
+        self.weight_1d(offset.x) * self.weight_1d(offset.y)
+    }
+
+    fn weight_1d(&self, x: f32) -> f32 {
+        match *self {
+            // Half open, so a sample on the edge between two pixels only counts once
+            Self::Box { radius } => f32::from(u8::from((-radius..radius).contains(&x))),
+            Self::Tent { radius } => (1.0 - x.abs() / radius).max(0.0),
+            Self::Gaussian { radius, sigma } => {
+                let gaussian = |x: f32| (-x * x / (2.0 * sigma * sigma)).exp();
+                (gaussian(x) - gaussian(radius)).max(0.0)
+            }
+            Self::Mitchell { radius, b, c } => mitchell(2.0 * x.abs() / radius, b, c),
+            Self::Lanczos { radius } if x.abs() < radius => sinc(x) * sinc(x / radius),
+            Self::Lanczos { .. } => 0.0,
+        }
+    }
+}
+
+fn mitchell(x: f32, b: f32, c: f32) -> f32 {
+    let (x2, x3) = (x * x, x * x * x);
+    let value = if x < 1.0 {
+        (12.0 - 9.0 * b - 6.0 * c) * x3 + (-18.0 + 12.0 * b + 6.0 * c) * x2 + (6.0 - 2.0 * b)
+    } else if x < 2.0 {
+        (-b - 6.0 * c) * x3
+            + (6.0 * b + 30.0 * c) * x2
+            + (-12.0 * b - 48.0 * c) * x
+            + (8.0 * b + 24.0 * c)
+    } else {
+        0.0
+    };
+    value / 6.0
+}
+
+fn sinc(x: f32) -> f32 {
+    if x.abs() < 1e-5 {
+        1.0
+    } else {
+        (PI * x).sin() / (PI * x)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filters_peak_at_center_and_vanish_at_radius() {
+        let filters = [
+            Filter::Box { radius: 1.0 },
+            Filter::Tent { radius: 1.5 },
+            Filter::Gaussian {
+                radius: 1.5,
+                sigma: 0.5,
+            },
+            Filter::Mitchell {
+                radius: 2.0,
+                b: 1.0 / 3.0,
+                c: 1.0 / 3.0,
+            },
+            Filter::Lanczos { radius: 3.0 },
+        ];
+        for filter in filters {
+            let radius = filter.radius();
+            let center = filter.weight(Vec2::ZERO);
+            assert!(center > 0.0, "{filter:?}");
+            for i in 1..=20 {
+                let x = radius * i as f32 / 20.0;
+                assert!(filter.weight(Vec2::new(x, 0.0)) <= center, "{filter:?}");
+            }
+            assert!(
+                filter.weight(Vec2::new(radius, 0.0)).abs() < 1e-5,
+                "{filter:?}"
+            );
+            assert!(filter.weight(Vec2::new(0.0, -radius - 0.1)).abs() < 1e-5);
+        }
+
+        let pixel = Filter::default();
+        assert!((pixel.weight(Vec2::splat(-0.5)) - 1.0).abs() < 1e-6);
+        assert!(pixel.weight(Vec2::new(0.5, 0.0)).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(Filter::default().validate().is_ok());
+        for radius in [0.0, -1.0, f32::NAN, f32::INFINITY] {
+            assert!(Filter::Tent { radius }.validate().is_err());
+        }
+        for sigma in [0.0, f32::NAN] {
+            let filter = Filter::Gaussian { radius: 1.5, sigma };
+            assert!(filter.validate().is_err());
+        }
+        let filter = Filter::Mitchell {
+            radius: 2.0,
+            b: f32::NAN,
+            c: 0.0,
+        };
+        assert!(filter.validate().is_err());
+    }
+}
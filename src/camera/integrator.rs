@@ -0,0 +1,304 @@
+use std::{
+    fmt::Debug,
+    hash::Hasher,
+};
+
+use glam::Vec3A as Vec3;
+
+use super::{
+    Camera,
+    Samples,
+};
+use crate::{
+    color::{
+        Color,
+        BLACK,
+        WHITE,
+    },
+    fingerprint::Fingerprint,
+    hittable::HitRecord,
+    timed_ray::TimedRay,
+};
+
+/// Turns a camera ray into the color seen along it.
+pub trait Integrator: Send + Sync + Debug + Fingerprint {
+    /// `samples` supplies the numbers for each bounce's scattering, light sampling and
+    /// Russian roulette.
+    fn color(&self, camera: &Camera, r: &TimedRay, samples: &mut Samples) -> Color;
+}
+
+/// Unidirectional path tracing with next-event estimation and multiple importance
+/// sampling.
+#[derive(Debug, Default)]
+pub struct PathTracer;
+
+impl Fingerprint for PathTracer {
+    fn fingerprint(&self, hasher: &mut dyn Hasher) {
+        "PathTracer".fingerprint(hasher);
+    }
+}
+
+impl Integrator for PathTracer {
+    fn color(&self, camera: &Camera, r: &TimedRay, samples: &mut Samples) -> Color {
+        let interval = 0.001..f32::MAX;
+        let mut radiance = BLACK;
+        let mut throughput = WHITE;
+        let mut ray = *r;
+        // The pdf of the material that sent `ray` choosing its direction, if sampled
+        // lights could also have picked it
+        let mut scatter_pdf = None;
+
+        for bounce in 0..camera.max_depth() {
+            // Even keys for the path's rays and odd ones for its shadow rays
+            let key = 2 * bounce as u64;
+            ray.key = samples.key(key);
+            let Some(hit_record) = camera.world().hit(&ray, &interval) else {
+                let background = camera.background().color(&ray, camera.stores());
+                radiance = radiance
+                    + throughput * Self::weigh_emitted(camera, background, &ray, scatter_pdf);
+                break;
+            };
+
+            let emitted = hit_record.material.emitted(&hit_record, camera.stores());
+            let emitted = Self::weigh_emitted(camera, emitted, &ray, scatter_pdf);
+            let (direct, lit) =
+                Self::direct_light(camera, &hit_record, samples.key(key + 1), samples);
+            radiance = radiance + throughput * (emitted + direct);
+
+            let Some((scattered, attenuation)) =
+                hit_record
+                    .material
+                    .scatter(&hit_record, samples.get_2d(), camera.stores())
+            else {
+                break;
+            };
+            scatter_pdf = lit.then(|| {
+                hit_record
+                    .material
+                    .scatter_pdf(&hit_record, scattered.direction, camera.stores())
+            });
+            throughput = throughput * attenuation;
+
+            if camera
+                .russian_roulette
+                .is_some_and(|min_depth| bounce + 1 >= min_depth)
+            {
+                // Paths that can only carry a little more light are likely to stop, and
+                // the survivors make up for them
+                let survival = throughput.0.max_element().min(1.0);
+                if samples.get_1d() >= survival {
+                    break;
+                }
+                throughput = throughput * (1.0 / survival);
+            }
+
+            ray = scattered;
+        }
+
+        radiance
+    }
+}
+
+impl PathTracer {
+    /// Weighs emission found by `r` against the chance that a sampled light already
+    /// found it, using multiple importance sampling.
+    fn weigh_emitted(
+        camera: &Camera,
+        emitted: Color,
+        r: &TimedRay,
+        scatter_pdf: Option<f32>,
+    ) -> Color {
+        match scatter_pdf {
+            Some(pdf) if emitted.0 != Vec3::ZERO => {
+                let light_pdf = camera.light_pdf(r.origin, r.direction, r.time);
+                emitted * power_heuristic(pdf, light_pdf)
+            }
+            _ => emitted,
+        }
+    }
+
+    /// Estimates the light arriving at the hit straight from a sampled light.
+    ///
+    /// Also returns whether the material could be lit this way, which isn't the case
+    /// for mirrors and glass or when there's nothing to sample.
+    fn direct_light(
+        camera: &Camera,
+        hit_record: &HitRecord,
+        shadow_key: u64,
+        samples: &mut Samples,
+    ) -> (Color, bool) {
+        if !camera.has_lights() {
+            return (BLACK, false);
+        }
+
+        let time = hit_record.in_ray.time;
+        let sample = camera.sample_light(hit_record.point, time, samples);
+        let direction = sample.map_or(hit_record.normal, |(direction, _)| direction);
+        let material = hit_record.material;
+        let Some(f) = material.eval(hit_record, direction, camera.stores()) else {
+            return (BLACK, false);
+        };
+        let Some((direction, pdf)) = sample.filter(|&(_, pdf)| pdf > 0.0) else {
+            return (BLACK, true);
+        };
+        if f.0 == Vec3::ZERO {
+            return (BLACK, true);
+        }
+
+        let shadow_ray = TimedRay::new(hit_record.point, direction, time).with_key(shadow_key);
+        let incoming = match camera.world().hit(&shadow_ray, &(0.001..f32::MAX)) {
+            Some(light_hit) => light_hit.material.emitted(&light_hit, camera.stores()),
+            None => camera.background().color(&shadow_ray, camera.stores()),
+        };
+        let scatter_pdf = material.scatter_pdf(hit_record, direction, camera.stores());
+        let weight = power_heuristic(pdf, scatter_pdf);
+        (f * incoming * (weight / pdf), true)
+    }
+}
+
+/// The multiple importance sampling weight for a sample taken with `pdf` that
+/// `other_pdf` could also have produced.
+fn power_heuristic(pdf: f32, other_pdf: f32) -> f32 {
+    let (a, b) = (pdf * pdf, other_pdf * other_pdf);
+    if a + b > 0.0 {
+        a / (a + b)
+    } else {
+        1.0
+    }
+}
+
+/// Shows the shading normal of the first hit.
+#[derive(Debug, Default)]
+pub struct Normals;
+
+impl Fingerprint for Normals {
+    fn fingerprint(&self, hasher: &mut dyn Hasher) {
+        "Normals".fingerprint(hasher);
+    }
+}
+
+impl Integrator for Normals {
+    fn color(&self, camera: &Camera, r: &TimedRay, _samples: &mut Samples) -> Color {
+        camera
+            .world
+            .hit(r, &(0.001..f32::MAX))
+            .map_or(BLACK, |hit_record| {
+                Color::from_unit_vector(hit_record.normal)
+            })
+    }
+}
+
+/// Shows the distance to the first hit, from white up close to black at `far`.
+#[derive(Debug, Default)]
+pub struct Depth {
+    far: Option<f32>,
+}
+
+impl Depth {
+    /// Without `far`, twice the distance from the camera to what it looks at is used.
+    pub fn new(far: Option<f32>) -> Self {
+        Self { far }
+    }
+}
+
+impl Fingerprint for Depth {
+    fn fingerprint(&self, hasher: &mut dyn Hasher) {
+        "Depth".fingerprint(hasher);
+        self.far.fingerprint(hasher);
+    }
+}
+
+impl Integrator for Depth {
+    fn color(&self, camera: &Camera, r: &TimedRay, _samples: &mut Samples) -> Color {
+        let Some(hit_record) = camera.world().hit(r, &(0.001..f32::MAX)) else {
+            return BLACK;
+        };
+        let far = self.far.unwrap_or(2.0 * camera.look_distance());
+        let distance = hit_record.t * r.direction.length();
+        let value = (1.0 - distance / far).max(0.0);
+        Color::new(value, value, value)
+    }
+}
+
+/// Shows the texture coordinates of the first hit as red and green.
+#[derive(Debug, Default)]
+pub struct Uv;
+
+impl Fingerprint for Uv {
+    fn fingerprint(&self, hasher: &mut dyn Hasher) {
+        "Uv".fingerprint(hasher);
+    }
+}
+
+impl Integrator for Uv {
+    fn color(&self, camera: &Camera, r: &TimedRay, _samples: &mut Samples) -> Color {
+        camera
+            .world
+            .hit(r, &(0.001..f32::MAX))
+            .map_or(BLACK, |hit_record| {
+                Color::new(hit_record.uv.x, hit_record.uv.y, 0.0)
+            })
+    }
+}
+
+/// Shows the attenuation of the first hit's material, or its emission for lights.
+#[derive(Debug, Default)]
+pub struct Albedo;
+
+impl Fingerprint for Albedo {
+    fn fingerprint(&self, hasher: &mut dyn Hasher) {
+        "Albedo".fingerprint(hasher);
+    }
+}
+
+impl Integrator for Albedo {
+    fn color(&self, camera: &Camera, r: &TimedRay, samples: &mut Samples) -> Color {
+        let Some(hit_record) = camera.world().hit(r, &(0.001..f32::MAX)) else {
+            return camera.background().color(r, camera.stores());
+        };
+        match hit_record
+            .material
+            .scatter(&hit_record, samples.get_2d(), camera.stores())
+        {
+            Some((_, attenuation)) => attenuation,
+            None => hit_record.material.emitted(&hit_record, camera.stores()),
+        }
+    }
+}
+
+/// Shows how many bounding boxes the first ray tested, from blue for none through
+/// green to red for `max_box_tests` or more.
+#[derive(Debug)]
+pub struct Heatmap {
+    max_box_tests: usize,
+}
+
+impl Heatmap {
+    pub fn new(max_box_tests: usize) -> Self {
+        assert!(max_box_tests > 0);
+        Self { max_box_tests }
+    }
+}
+
+impl Default for Heatmap {
+    fn default() -> Self {
+        Self::new(100)
+    }
+}
+
+impl Fingerprint for Heatmap {
+    fn fingerprint(&self, hasher: &mut dyn Hasher) {
+        "Heatmap".fingerprint(hasher);
+        self.max_box_tests.fingerprint(hasher);
+    }
+}
+
+impl Integrator for Heatmap {
+    fn color(&self, camera: &Camera, r: &TimedRay, _samples: &mut Samples) -> Color {
+        let mut box_tests = 0;
+        camera
+            .world()
+            .hit_counting(r, &(0.001..f32::MAX), &mut box_tests);
+        Color::heat(box_tests as f32 / self.max_box_tests as f32)
+    }
+}
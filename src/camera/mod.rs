@@ -1,48 +1,96 @@
+mod adaptive;
+mod background;
 mod builder;
+mod checkpoint;
+mod film;
+mod filter;
+mod integrator;
+mod sampler;
 
 use std::{
-    fs::{
-        self,
-        create_dir,
+    hash::Hasher,
+    iter,
+    ops::ControlFlow,
+    path::{
+        Path,
+        PathBuf,
     },
-    ops::Range,
     time::{
+        Duration,
         Instant,
-        SystemTime,
     },
 };
 
+pub use adaptive::Adaptive;
+pub use background::Background;
 pub use builder::Builder;
-use chrono::DateTime;
-use glam::Vec3A as Vec3;
-use image::{
-    save_buffer,
-    ColorType,
+use checkpoint::Fnv;
+pub use checkpoint::{
+    Checkpoint,
+    CheckpointError,
+};
+pub use film::Film;
+use film::Splat;
+pub use filter::Filter;
+use glam::{
+    Vec2,
+    Vec3A as Vec3,
 };
-use indicatif::{
-    ProgressBar,
-    ProgressIterator,
+use image::ImageResult;
+use indicatif::ProgressBar;
+pub use integrator::{
+    Albedo,
+    Depth,
+    Heatmap,
+    Integrator,
+    Normals,
+    PathTracer,
+    Uv,
 };
-use itertools::Itertools;
 use rayon::prelude::*;
+pub use sampler::{
+    Halton,
+    Independent,
+    SampleIndex,
+    Sampler,
+    Samples,
+    Sobol,
+    Stratified,
+};
 
 use crate::{
-    color::{
-        Color,
-        BLACK,
-        LIGHT_BLUE,
-        WHITE,
-    },
+    color::Color,
     extension_traits::Vec3Ext,
-    hittable::Hittable,
-    rng::random_range,
+    fingerprint::Fingerprint,
+    hittable::{
+        Hittable,
+        HittableList,
+    },
+    output::{
+        self,
+        Format,
+    },
     texture::TextureStore,
     timed_ray::TimedRay,
+    tonemap::ToneMap,
 };
 
-#[derive(Default)]
+/// How many rows of pixels are rendered between adding their samples to the film.
+const BAND_ROWS: usize = 16;
+
+#[derive(Debug, Default)]
 pub struct Stores {
     pub textures: TextureStore,
+    /// Shapes to sample directly for light. They are only used to pick directions, so
+    /// each should match an emitter in the world.
+    pub lights: HittableList,
+}
+
+impl Fingerprint for Stores {
+    fn fingerprint(&self, hasher: &mut dyn Hasher) {
+        self.textures.fingerprint(hasher);
+        self.lights.fingerprint(hasher);
+    }
 }
 
 pub struct Camera {
@@ -59,56 +107,215 @@ pub struct Camera {
     defocus_dist_u: Vec3,
     defocus_dist_v: Vec3,
     quiet: bool,
+    background: Background,
+    russian_roulette: Option<usize>,
+    integrator: Box<dyn Integrator>,
+    /// Distance from the camera to the point it looks at.
+    look_distance: f32,
+    tone_map: ToneMap,
+    seed: u64,
+    sampler: Box<dyn Sampler>,
+    filter: Filter,
+    adaptive: Option<Adaptive>,
+    checkpoint: Option<PathBuf>,
+    checkpoint_interval: Duration,
 }
 
 impl Camera {
-    fn get_filename() -> String {
-        let now = SystemTime::now();
-        let datetime = DateTime::<chrono::Local>::from(now);
-        let timestamp = datetime.format("%y%m%d_%H%M%S").to_string();
-        format!("{timestamp}.png")
-    }
-
-    pub fn render_to_file(&self) {
-        let prev_filename = "last_run.png";
-        let buf: Vec<_> = self.render().iter().flat_map(Color::bytes).collect();
-        save_buffer(
-            prev_filename,
-            &buf,
-            self.width as u32,
-            self.height as u32,
-            ColorType::Rgb8,
-        )
-        .unwrap();
-
-        let dir = std::path::Path::new("out");
-        if !dir.exists() {
-            create_dir(dir).unwrap();
+    /// Renders the image and saves it to `path`, tone mapped unless `format` can hold
+    /// the raw radiance.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the image can't be written.
+    pub fn render_to_file(&self, path: impl AsRef<Path>, format: Format) -> ImageResult<()> {
+        self.save(&self.render_film(), path, format)
+    }
+
+    /// Saves a rendered film to `path`, tone mapped unless `format` can hold the raw
+    /// radiance.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the image can't be written.
+    pub fn save(&self, film: &Film, path: impl AsRef<Path>, format: Format) -> ImageResult<()> {
+        let mut pixels = film.pixels();
+        if !format.is_hdr() {
+            for pixel in &mut pixels {
+                *pixel = self.tone_map.apply(*pixel);
+            }
         }
-        let filename = Self::get_filename();
-        let path = dir.join(filename);
-        fs::copy(prev_filename, path).unwrap();
+        output::save(&pixels, self.width, self.height, path.as_ref(), format)
     }
 
-    fn sample_ray_origin(&self) -> Vec3 {
-        let p = Vec3::random_in_unit_disk();
+    fn sample_ray_origin(&self, u: Vec2) -> Vec3 {
+        let p = Vec3::sample_unit_disk(u);
         self.camera_center + self.defocus_dist_u * p.x + self.defocus_dist_v * p.y
     }
 
-    fn pixel_color(&self, x: usize, y: usize) -> Color {
-        let samples: Vec<_> = (0..self.samples_per_pixel)
-            .map(|_| {
-                let ray_origin = self.sample_ray_origin();
-                let ray_direction = self.sample_location(x, y) - ray_origin;
-                let ray_time = fastrand::f32();
-                let ray = TimedRay::new(ray_origin, ray_direction, ray_time);
-                self.color(&ray, self.max_depth)
-            })
-            .collect();
-        Color::average(&samples)
+    /// Brings the pixel up to the samples the current pass of `film` asks of it.
+    fn render_pixel(&self, x: usize, y: usize, film: &Film) -> Splat {
+        let pixel = y * self.width + x;
+        let stats = film.stats(pixel);
+        let target = film.target(pixel);
+        // Adaptive rounds can't know how many samples a pixel will end up with, so
+        // each round's samples are spread evenly among themselves
+        let samples_per_pixel = if self.adaptive.is_some() {
+            target.saturating_sub(stats.count())
+        } else {
+            self.samples_per_pixel
+        };
+        let mut splat = Splat::new(x, y, &self.filter, stats);
+        for sample in stats.count()..target {
+            let index = SampleIndex {
+                seed: self.seed,
+                pixel,
+                sample,
+                samples_per_pixel,
+            };
+            let mut samples = Samples::new(self.sampler.as_ref(), index);
+            let offset = samples.get_2d() - Vec2::splat(0.5);
+            let location = self.sample_location(x, y, offset);
+            let ray_origin = self.sample_ray_origin(samples.get_2d());
+            let ray_time = samples.get_1d();
+            let ray =
+                TimedRay::new(ray_origin, location - ray_origin, ray_time).with_key(samples.key(0));
+            let color = self.integrator.color(self, &ray, &mut samples);
+            splat.add(&self.filter, offset, color);
+        }
+        splat
     }
 
     pub fn render(&self) -> Vec<Color> {
+        self.render_film().pixels()
+    }
+
+    /// Renders the image, keeping the sample counts along with the pixels.
+    pub fn render_film(&self) -> Film {
+        let film = Film::new(self.width, self.height);
+        self.render_passes(film, false, |_, _| ControlFlow::Continue(()))
+    }
+
+    /// Renders the image in passes that each double the samples per pixel, calling
+    /// `on_pass` after each with the film so far and the samples per pixel it has
+    /// reached on average, which can break off the render. With adaptive sampling,
+    /// each round is a pass.
+    pub fn render_progressive(&self, on_pass: impl FnMut(&Film, usize) -> ControlFlow<()>) -> Film {
+        let film = Film::new(self.width, self.height);
+        self.render_passes(film, true, on_pass)
+    }
+
+    /// How many samples all pixels may take together.
+    fn budget(&self) -> usize {
+        let samples_per_pixel = match self.adaptive {
+            Some(adaptive) => self.samples_per_pixel.max(adaptive.min_samples),
+            None => self.samples_per_pixel,
+        };
+        samples_per_pixel * self.width * self.height
+    }
+
+    /// Sets up the pass after the one `film` has finished, returning `false` if the
+    /// render is done.
+    fn plan_pass(&self, film: &mut Film, progressive: bool) -> bool {
+        let targets = if let Some(adaptive) = self.adaptive {
+            adaptive.next_targets(film, self.budget())
+        } else {
+            let done = film.min_sample_count();
+            let next = if progressive {
+                iter::successors(Some(1), |samples| Some(samples * 2))
+                    .find(|&samples| samples > done)
+                    .map_or(self.samples_per_pixel, |samples| {
+                        samples.min(self.samples_per_pixel)
+                    })
+            } else {
+                self.samples_per_pixel
+            };
+            (next > done).then(|| vec![next; self.width * self.height])
+        };
+        targets.map(|targets| film.set_targets(targets)).is_some()
+    }
+
+    /// Carries on with the render saved in `checkpoint`, progressively or not, and
+    /// ends up with the same image as if it had never stopped. `on_pass` is called as
+    /// with [`Camera::render_progressive`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the checkpoint was made with a different scene, camera
+    /// settings or seed.
+    pub fn resume(
+        &self,
+        checkpoint: Checkpoint,
+        progressive: bool,
+        on_pass: impl FnMut(&Film, usize) -> ControlFlow<()>,
+    ) -> Result<Film, CheckpointError> {
+        let film = &checkpoint.film;
+        if checkpoint.seed != self.seed
+            || checkpoint.scene_hash != self.scene_hash()
+            || (film.width(), film.height()) != (self.width, self.height)
+        {
+            return Err(CheckpointError::Mismatch);
+        }
+        Ok(self.render_passes(checkpoint.film, progressive, on_pass))
+    }
+
+    /// Identifies everything that goes into the rendered radiance, so a checkpoint
+    /// can't be resumed with a different scene or settings. The seed is checked
+    /// separately.
+    pub fn scene_hash(&self) -> u64 {
+        let mut hasher = Fnv::default();
+        let values: [&dyn Fingerprint; 19] = [
+            &self.world,
+            &self.stores,
+            &self.background,
+            &self.width,
+            &self.height,
+            &self.samples_per_pixel,
+            &self.max_depth,
+            &self.camera_center,
+            &self.pixel00_loc,
+            &self.pixel_delta_u,
+            &self.pixel_delta_v,
+            &self.defocus_dist_u,
+            &self.defocus_dist_v,
+            &self.russian_roulette,
+            &self.look_distance,
+            &self.filter,
+            &self.adaptive,
+            &self.integrator,
+            &self.sampler,
+        ];
+        for value in values {
+            value.fingerprint(&mut hasher);
+        }
+        hasher.finish()
+    }
+
+    /// Saves a checkpoint if one is asked for, warning rather than stopping the render
+    /// if it fails.
+    fn save_checkpoint(&self, film: Film, scene_hash: u64) -> Film {
+        let Some(path) = &self.checkpoint else {
+            return film;
+        };
+        let checkpoint = Checkpoint {
+            seed: self.seed,
+            scene_hash,
+            film,
+        };
+        if let Err(error) = checkpoint.save(path) {
+            eprintln!("failed to save checkpoint {}: {error}", path.display());
+        }
+        checkpoint.film
+    }
+
+    /// Finishes the pass `film` is partway through, if any, and renders passes until
+    /// the plan runs out or `on_pass` breaks off.
+    fn render_passes(
+        &self,
+        mut film: Film,
+        progressive: bool,
+        mut on_pass: impl FnMut(&Film, usize) -> ControlFlow<()>,
+    ) -> Film {
         if !self.quiet {
             println!("Rendering...");
         }
@@ -116,57 +323,392 @@ impl Camera {
         let progress_bar = if self.quiet {
             ProgressBar::hidden()
         } else {
-            ProgressBar::new((self.height * self.width) as u64)
+            ProgressBar::new(self.budget() as u64)
         };
+        progress_bar.set_position(film.total_samples() as u64);
 
         let start = Instant::now();
-        let pixels: Vec<_> = (0..self.height)
-            .cartesian_product(0..self.width)
-            .progress_with(progress_bar)
-            .par_bridge()
-            .map(|(y, x)| (x, y, self.pixel_color(x, y)))
-            .collect();
+        let scene_hash = if self.checkpoint.is_some() {
+            self.scene_hash()
+        } else {
+            0
+        };
+        let mut last_checkpoint = Instant::now();
+        while !film.is_pass_done() || self.plan_pass(&mut film, progressive) {
+            // Splats overlap, so they go on the film in order to keep the sums the
+            // same whichever thread renders what. Working in bands of rows caps how
+            // many are held at once.
+            for band in (0..self.height).step_by(BAND_ROWS) {
+                let rows = band..(band + BAND_ROWS).min(self.height);
+                let splats: Vec<_> = (rows.start * self.width..rows.end * self.width)
+                    .into_par_iter()
+                    .map(|pixel| {
+                        let (x, y) = (pixel % self.width, pixel / self.width);
+                        let splat = self.render_pixel(x, y, &film);
+                        let taken = splat.stats.count() - film.stats(pixel).count();
+                        progress_bar.inc(taken as u64);
+                        splat
+                    })
+                    .collect();
+                for splat in &splats {
+                    film.add(splat);
+                }
+                // Pixels only ever take the samples they're missing, so a checkpoint
+                // can be taken partway through a pass
+                if last_checkpoint.elapsed() >= self.checkpoint_interval {
+                    film = self.save_checkpoint(film, scene_hash);
+                    last_checkpoint = Instant::now();
+                }
+            }
+            film = self.save_checkpoint(film, scene_hash);
+            last_checkpoint = Instant::now();
+            let samples = film.total_samples() / (self.width * self.height);
+            if on_pass(&film, samples).is_break() {
+                break;
+            }
+        }
+        progress_bar.finish();
 
         if !self.quiet {
             println!("Done in {:?}", start.elapsed());
         }
 
-        let mut result = vec![Color::default(); self.height * self.width];
-        for (x, y, color) in pixels {
-            result[y * self.width + x] = color;
-        }
-        result
+        film
     }
 
-    fn sample_location(&self, x: usize, y: usize) -> Vec3 {
-        let rand_x = random_range(&(-0.5..0.5));
-        let rand_y = random_range(&(-0.5..0.5));
+    /// The point in the focus plane `offset` from the center of pixel (`x`, `y`).
+    fn sample_location(&self, x: usize, y: usize, offset: Vec2) -> Vec3 {
         self.pixel00_loc
-            + (self.pixel_delta_u * (x as f32 + rand_x))
-            + (self.pixel_delta_v * (y as f32 + rand_y))
+            + (self.pixel_delta_u * (x as f32 + offset.x))
+            + (self.pixel_delta_v * (y as f32 + offset.y))
+    }
+
+    pub fn world(&self) -> &dyn Hittable {
+        self.world.as_ref()
+    }
+
+    pub fn stores(&self) -> &Stores {
+        &self.stores
+    }
+
+    pub fn background(&self) -> &Background {
+        &self.background
     }
 
-    pub fn bounce(&self, r: &TimedRay, interval: &Range<f32>) -> Option<(TimedRay, Color)> {
-        let hit_record = self.world.hit(r, interval)?;
-        hit_record.material.scatter(&hit_record, &self.stores)
+    pub fn max_depth(&self) -> usize {
+        self.max_depth
     }
 
-    pub fn color(&self, r: &TimedRay, depth: usize) -> Color {
-        if depth == 0 {
-            return BLACK;
+    /// The depth after which paths may be ended at random, if at all.
+    pub fn russian_roulette(&self) -> Option<usize> {
+        self.russian_roulette
+    }
+
+    /// Distance from the camera to the point it looks at.
+    pub fn look_distance(&self) -> f32 {
+        self.look_distance
+    }
+
+    /// Whether there are light shapes or an environment map to sample directly.
+    pub fn has_lights(&self) -> bool {
+        !self.stores.lights.objects.is_empty()
+            || matches!(self.background, Background::Environment(_))
+    }
+
+    /// Picks a direction towards either a light shape or the environment map, and
+    /// returns it with its [`Camera::light_pdf`]. Always takes the same dimensions from
+    /// `samples`, whichever it picks.
+    pub fn sample_light(
+        &self,
+        origin: Vec3,
+        time: f32,
+        samples: &mut Samples,
+    ) -> Option<(Vec3, f32)> {
+        let shapes = &self.stores.lights;
+        let choice = samples.get_1d();
+        let u = samples.get_2d();
+        let sample_environment = match &self.background {
+            Background::Environment(environment) if shapes.objects.is_empty() || choice < 0.5 => {
+                Some(environment)
+            }
+            _ => None,
+        };
+        let direction = match sample_environment {
+            Some(environment) => environment.sample(u).0,
+            None => shapes.sample(origin, time, u)?.0,
+        };
+        Some((direction, self.light_pdf(origin, direction, time)))
+    }
+
+    /// The pdf with respect to solid angle of [`Camera::sample_light`] picking
+    /// `direction`.
+    pub fn light_pdf(&self, origin: Vec3, direction: Vec3, time: f32) -> f32 {
+        let shapes = &self.stores.lights;
+        let shapes_pdf =
+            (!shapes.objects.is_empty()).then(|| shapes.pdf_value(origin, direction, time));
+        let environment_pdf = match &self.background {
+            Background::Environment(environment) => Some(environment.pdf(direction)),
+            _ => None,
+        };
+        match (shapes_pdf, environment_pdf) {
+            (Some(shapes_pdf), Some(environment_pdf)) => 0.5 * (shapes_pdf + environment_pdf),
+            (Some(pdf), None) | (None, Some(pdf)) => pdf,
+            (None, None) => 0.0,
         }
+    }
+}
 
-        let interval = 0.001..f32::MAX;
-        if let Some((scattered, attenuation)) = self.bounce(r, &interval) {
-            attenuation * self.color(&scattered, depth - 1)
-        } else {
-            Self::background(r)
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        color::WHITE,
+        environment::EnvironmentMap,
+        hittable::{
+            Sphere,
+            TriangleMesh,
+        },
+        material::{
+            Dielectric,
+            DiffuseLight,
+            Lambertian,
+            Metal,
+        },
+        texture::SolidColor,
+    };
+
+    /// A glass ball between a lit ground and the sky.
+    fn scene() -> Builder {
+        let mut world = HittableList::default();
+        let mut stores = Stores::default();
+        let grey = stores.textures.add(SolidColor::new(0.5, 0.5, 0.5));
+        let light = stores.textures.add(SolidColor::new(4.0, 4.0, 4.0));
+        world.add(Sphere::new_static(
+            Vec3::new(0.0, -100.5, -1.0),
+            100.0,
+            Lambertian::new(grey),
+        ));
+        world.add(Sphere::new_static(
+            Vec3::new(0.0, 0.0, -1.0),
+            0.5,
+            Dielectric::new(1.5),
+        ));
+        world.add(Sphere::new_static(
+            Vec3::new(0.0, 2.0, -1.0),
+            0.5,
+            DiffuseLight::new(light),
+        ));
+        stores.lights.add(Sphere::new_static(
+            Vec3::new(0.0, 2.0, -1.0),
+            0.5,
+            DiffuseLight::new(light),
+        ));
+        Builder::new(world, stores)
+            .width(16)
+            .max_depth(8)
+            .quiet(true)
+            .seed(42)
+    }
+
+    fn bits(pixels: &[Color]) -> Vec<u32> {
+        pixels
+            .iter()
+            .flat_map(|color| color.0.to_array().map(f32::to_bits))
+            .collect()
+    }
+
+    #[test]
+    fn test_same_seed_renders_identically_on_any_thread_count() {
+        let camera = scene().samples_per_pixel(4).build();
+        let render = |threads| {
+            rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()
+                .unwrap()
+                .install(|| camera.render())
+        };
+
+        assert_eq!(bits(&render(1)), bits(&render(3)));
+    }
+
+    #[test]
+    fn test_rendering_leaves_the_global_generator_alone() {
+        let camera = scene().samples_per_pixel(4).build();
+        let mut film = Film::new(camera.width, camera.height);
+        assert!(camera.plan_pass(&mut film, false));
+        fastrand::seed(7);
+        // The bottom row looks at the ground, which samples the light
+        camera.render_pixel(0, camera.height - 1, &film);
+        assert_eq!(fastrand::u64(..), fastrand::Rng::with_seed(7).u64(..));
+    }
+
+    #[test]
+    fn test_light_sampling_takes_the_same_dimensions_every_time() {
+        /// Numbers that give away which dimension they came from.
+        #[derive(Debug)]
+        struct Dimensions;
+
+        impl Fingerprint for Dimensions {
+            fn fingerprint(&self, _hasher: &mut dyn Hasher) {}
+        }
+
+        impl Sampler for Dimensions {
+            fn get_2d(&self, _index: &SampleIndex, dimension: usize) -> Vec2 {
+                Vec2::splat(dimension as f32 / 16.0)
+            }
         }
+
+        let camera = scene().build();
+        let mut samples = Samples::new(&Dimensions, SampleIndex::default());
+        // From inside the light there is nothing to sample
+        for origin in [Vec3::ZERO, Vec3::new(0.0, 2.0, -1.0)] {
+            let dimension = samples.get_1d() * 16.0;
+            camera.sample_light(origin, 0.0, &mut samples);
+            assert_eq!((samples.get_1d() * 16.0 - dimension) as usize, 3);
+        }
+    }
+
+    #[test]
+    fn test_progressive_passes_add_up_to_a_full_render() {
+        let camera = scene().samples_per_pixel(12).build();
+        let mut passes = Vec::new();
+        let progressive = camera.render_progressive(|film, samples| {
+            assert!(film.sample_counts().iter().all(|&count| count == samples));
+            passes.push(samples);
+            ControlFlow::Continue(())
+        });
+        assert_eq!(passes, [1, 2, 4, 8, 12]);
+
+        for (a, b) in progressive.pixels().iter().zip(camera.render()) {
+            assert!((a.0 - b.0).abs().max_element() <= 1e-4 * b.0.max_element().max(1.0));
+        }
+    }
+
+    #[test]
+    fn test_adaptive_sampling_spends_samples_on_noise() {
+        let adaptive = Adaptive {
+            min_samples: 8,
+            max_samples: 256,
+            threshold: 0.02,
+        };
+        let film = scene()
+            .samples_per_pixel(32)
+            .adaptive(Some(adaptive))
+            .build()
+            .render_film();
+        let counts = film.sample_counts();
+        assert!(counts.iter().all(|count| (8..=256).contains(count)));
+        assert_eq!(counts.iter().sum::<usize>(), 32 * counts.len());
+
+        // The sky along the top is smooth, while the ground along the bottom is lit by
+        // randomly sampled light
+        let width = film.width();
+        let top = &counts[..width];
+        let bottom = &counts[counts.len() - width..];
+        assert!(top.iter().sum::<usize>() * 2 < bottom.iter().sum::<usize>());
     }
 
-    pub fn background(r: &TimedRay) -> Color {
-        let unit_direction = r.direction.normalize();
-        let t = 0.5 * (unit_direction.y + 1.0);
-        WHITE.lerp(&LIGHT_BLUE, t)
+    #[test]
+    fn test_adaptive_rounds_carry_on_after_stopping_partway() {
+        let adaptive = Adaptive {
+            min_samples: 4,
+            max_samples: 64,
+            threshold: 0.02,
+        };
+        let camera = scene()
+            .width(40)
+            .samples_per_pixel(16)
+            .adaptive(Some(adaptive))
+            .build();
+        let film = Film::new(camera.width, camera.height);
+        let mut film = camera.render_passes(film, true, |_, _| ControlFlow::Break(()));
+        // Stop after the first row of the next round, as if the render had been killed
+        assert!(camera.plan_pass(&mut film, true));
+        for x in 0..camera.width {
+            let splat = camera.render_pixel(x, 0, &film);
+            film.add(&splat);
+        }
+
+        let resumed = camera.render_passes(film, true, |_, _| ControlFlow::Continue(()));
+        let uninterrupted = camera.render_film();
+        assert_eq!(resumed.sample_counts(), uninterrupted.sample_counts());
+        assert_eq!(bits(&resumed.pixels()), bits(&uninterrupted.pixels()));
+    }
+
+    #[test]
+    fn test_resumed_render_matches_an_uninterrupted_one() {
+        let name = format!("ray-tracing-checkpoint-test-{}.ckpt", std::process::id());
+        let path = std::env::temp_dir().join(name);
+        let camera = scene()
+            .samples_per_pixel(12)
+            .checkpoint(Some(path.clone()))
+            .build();
+        // Stop after the pass with 4 samples, as if the render had been killed
+        camera.render_progressive(|_, samples| {
+            if samples < 4 {
+                ControlFlow::Continue(())
+            } else {
+                ControlFlow::Break(())
+            }
+        });
+
+        let mut passes = Vec::new();
+        let resumed = camera
+            .resume(Checkpoint::load(&path).unwrap(), true, |_, samples| {
+                passes.push(samples);
+                ControlFlow::Continue(())
+            })
+            .unwrap();
+        assert_eq!(passes, [8, 12]);
+        let uninterrupted = scene()
+            .samples_per_pixel(12)
+            .build()
+            .render_progressive(|_, _| ControlFlow::Continue(()));
+        assert_eq!(bits(&resumed.pixels()), bits(&uninterrupted.pixels()));
+
+        let other_seed = scene().samples_per_pixel(12).seed(7).build();
+        let checkpoint = Checkpoint::load(&path).unwrap();
+        assert!(matches!(
+            other_seed.resume(checkpoint, true, |_, _| ControlFlow::Continue(())),
+            Err(CheckpointError::Mismatch)
+        ));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_resume_rejects_a_changed_mesh_or_environment() {
+        let build = |apex: f32, sky: f32| {
+            let mesh = TriangleMesh::new(
+                vec![
+                    Vec3::new(-1.0, 0.0, -2.0),
+                    Vec3::new(1.0, 0.0, -2.0),
+                    Vec3::new(0.0, apex, -2.0),
+                ],
+                vec![[0, 1, 2]],
+                None,
+                None,
+                Metal::new(WHITE, 0.0),
+            );
+            let environment = EnvironmentMap::new(2, 1, vec![Color::new(sky, sky, sky), WHITE]);
+            Builder::new(mesh, Stores::default())
+                .width(16)
+                .quiet(true)
+                .seed(42)
+                .background(Background::Environment(environment))
+                .build()
+        };
+        let camera = build(1.0, 1.0);
+        assert_eq!(build(1.0, 1.0).scene_hash(), camera.scene_hash());
+        for changed in [build(2.0, 1.0), build(1.0, 2.0)] {
+            let checkpoint = Checkpoint {
+                seed: 42,
+                scene_hash: camera.scene_hash(),
+                film: Film::new(camera.width, camera.height),
+            };
+            assert!(matches!(
+                changed.resume(checkpoint, false, |_, _| ControlFlow::Continue(())),
+                Err(CheckpointError::Mismatch)
+            ));
+        }
     }
 }
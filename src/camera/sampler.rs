@@ -0,0 +1,316 @@
+use std::{
+    fmt::Debug,
+    hash::Hasher,
+};
+
+use glam::Vec2;
+
+use crate::{
+    fingerprint::Fingerprint,
+    rng,
+};
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SampleIndex {
+    pub seed: u64,
+    pub pixel: usize,
+    pub sample: usize,
+    /// How many samples are taken together. Samplers that need to know in advance
+    /// spread any run of this many consecutive samples evenly.
+    pub samples_per_pixel: usize,
+}
+
+/// Each dimension of a sample, like the point in the pixel or on the lens, is a pair
+/// of numbers in `[0, 1)` that good samplers spread evenly over a pixel's samples.
+pub trait Sampler: Send + Sync + Debug + Fingerprint {
+    fn get_2d(&self, index: &SampleIndex, dimension: usize) -> Vec2;
+}
+
+/// Hands out the pairs of one sample, a dimension at a time.
+pub struct Samples<'a> {
+    sampler: &'a dyn Sampler,
+    index: SampleIndex,
+    dimension: usize,
+}
+
+/// Independent numbers, for tracing rays outside of a render.
+impl Default for Samples<'static> {
+    fn default() -> Self {
+        Self::new(&Independent, SampleIndex::default())
+    }
+}
+
+impl<'a> Samples<'a> {
+    pub fn new(sampler: &'a dyn Sampler, index: SampleIndex) -> Self {
+        Self {
+            sampler,
+            index,
+            dimension: 0,
+        }
+    }
+
+    pub fn get_2d(&mut self) -> Vec2 {
+        let u = self.sampler.get_2d(&self.index, self.dimension);
+        self.dimension += 1;
+        u
+    }
+
+    /// A number unique to this sample and `salt`, for keying random choices that can't
+    /// take a dimension because paths make different numbers of them.
+    pub fn key(&self, salt: u64) -> u64 {
+        // One more value than `key` hashes keeps the two from ever agreeing
+        let index = &self.index;
+        rng::hash(&[index.seed, index.pixel as u64, index.sample as u64, salt, 0])
+    }
+
+    /// Uses up a whole dimension, so that later ones line up across samples.
+    pub fn get_1d(&mut self) -> f32 {
+        self.get_2d().x
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Independent;
+
+impl Fingerprint for Independent {
+    fn fingerprint(&self, hasher: &mut dyn Hasher) {
+        "Independent".fingerprint(hasher);
+    }
+}
+
+impl Sampler for Independent {
+    fn get_2d(&self, index: &SampleIndex, dimension: usize) -> Vec2 {
+        let mut rng = fastrand::Rng::with_seed(key(index, dimension, index.sample));
+        Vec2::new(rng.f32(), rng.f32())
+    }
+}
+
+/// Splits the square into a grid with a cell for each sample and jitters a point
+/// within each, visiting the cells in a different order for every dimension.
+#[derive(Debug, Default)]
+pub struct Stratified;
+
+impl Fingerprint for Stratified {
+    fn fingerprint(&self, hasher: &mut dyn Hasher) {
+        "Stratified".fingerprint(hasher);
+    }
+}
+
+impl Sampler for Stratified {
+    fn get_2d(&self, index: &SampleIndex, dimension: usize) -> Vec2 {
+        let count = index.samples_per_pixel.max(1);
+        let columns = (count as f32).sqrt().ceil() as usize;
+        let rows = count.div_ceil(columns);
+        let sample = index.sample % count;
+
+        let order = key(index, dimension, usize::MAX);
+        let cell = permute(sample as u32, count as u32, order as u32) as usize;
+        let mut rng = fastrand::Rng::with_seed(key(index, dimension, index.sample));
+        Vec2::new(
+            ((cell % columns) as f32 + rng.f32()) / columns as f32,
+            ((cell / columns) as f32 + rng.f32()) / rows as f32,
+        )
+    }
+}
+
+/// The Halton sequence with a pair of prime bases per dimension, its digits scrambled
+/// differently for every pixel. Dimensions past the last base fall back to
+/// [`Independent`].
+#[derive(Debug, Default)]
+pub struct Halton;
+
+const PRIMES: [u32; 32] = [
+    2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89, 97,
+    101, 103, 107, 109, 113, 127, 131,
+];
+
+impl Fingerprint for Halton {
+    fn fingerprint(&self, hasher: &mut dyn Hasher) {
+        "Halton".fingerprint(hasher);
+    }
+}
+
+impl Sampler for Halton {
+    fn get_2d(&self, index: &SampleIndex, dimension: usize) -> Vec2 {
+        let Some(&[x_base, y_base]) = PRIMES.get(2 * dimension..2 * dimension + 2) else {
+            return Independent.get_2d(index, dimension);
+        };
+        let sample = index.sample as u64;
+        Vec2::new(
+            scrambled_radical_inverse(x_base, sample, key(index, dimension, 0)),
+            scrambled_radical_inverse(y_base, sample, key(index, dimension, 1)),
+        )
+    }
+}
+
+/// The first two dimensions of the Sobol sequence with Owen scrambling, following
+/// Burley's "Practical Hash-based Owen Scrambling". Every dimension reuses them with
+/// its own scrambling and order of points.
+#[derive(Debug, Default)]
+pub struct Sobol;
+
+impl Fingerprint for Sobol {
+    fn fingerprint(&self, hasher: &mut dyn Hasher) {
+        "Sobol".fingerprint(hasher);
+    }
+}
+
+impl Sampler for Sobol {
+    fn get_2d(&self, index: &SampleIndex, dimension: usize) -> Vec2 {
+        let seeds = key(index, dimension, 0);
+        let sample = nested_uniform_scramble(index.sample as u32, seeds as u32);
+        let x = nested_uniform_scramble(sample.reverse_bits(), (seeds >> 32) as u32);
+        let y = nested_uniform_scramble(
+            sobol_second_dimension(sample),
+            key(index, dimension, 1) as u32,
+        );
+        Vec2::new(to_unit(x), to_unit(y))
+    }
+}
+
+/// A seed for one dimension of one pixel, and of one sample unless `salt` stands in.
+fn key(index: &SampleIndex, dimension: usize, salt: usize) -> u64 {
+    rng::hash(&[
+        index.seed,
+        index.pixel as u64,
+        dimension as u64,
+        salt as u64,
+    ])
+}
+
+/// The position of `i` in a shuffle of `0..len` chosen by `seed`, from Kensler's
+/// "Correlated Multi-Jittered Sampling".
+fn permute(mut i: u32, len: u32, seed: u32) -> u32 {
+    let mut mask = len - 1;
+    mask |= mask >> 1;
+    mask |= mask >> 2;
+    mask |= mask >> 4;
+    mask |= mask >> 8;
+    mask |= mask >> 16;
+    // Shuffles within the next power of two, and repeats until the result is in range
+    loop {
+        i ^= seed;
+        i = i.wrapping_mul(0xe170_893d);
+        i ^= seed >> 16;
+        i ^= (i & mask) >> 4;
+        i ^= seed >> 8;
+        i = i.wrapping_mul(0x0929_eb3f);
+        i ^= seed >> 23;
+        i ^= (i & mask) >> 1;
+        i = i.wrapping_mul(1 | seed >> 27);
+        i = i.wrapping_mul(0x6935_fa69);
+        i ^= (i & mask) >> 11;
+        i = i.wrapping_mul(0x74dc_b303);
+        i ^= (i & mask) >> 2;
+        i = i.wrapping_mul(0x9e50_1cc3);
+        i ^= (i & mask) >> 2;
+        i = i.wrapping_mul(0xc860_a3df);
+        i &= mask;
+        i ^= i >> 5;
+        if i < len {
+            return (i + seed % len) % len;
+        }
+    }
+}
+
+/// Mirrors the digits of `index` in `base` around the point, shifting each digit by an
+/// amount picked by `seed`.
+fn scrambled_radical_inverse(base: u32, mut index: u64, seed: u64) -> f32 {
+    let base = u64::from(base);
+    let mut scale = 1.0 / base as f64;
+    let mut result = 0.0;
+    let mut position = 0;
+    // Digits past the end of `index` are zero, but scrambling still spreads them out
+    while scale > 1e-8 {
+        let digit = (index % base + rng::hash(&[seed, position]) % base) % base;
+        result += digit as f64 * scale;
+        index /= base;
+        scale /= base as f64;
+        position += 1;
+    }
+    (result as f32).min(1.0 - f32::EPSILON / 2.0)
+}
+
+/// The Sobol sequence's second dimension, whose direction numbers each come from the
+/// last by `v ^ (v >> 1)`.
+fn sobol_second_dimension(mut index: u32) -> u32 {
+    let mut result = 0;
+    let mut direction = 1 << 31;
+    while index != 0 {
+        if index & 1 != 0 {
+            result ^= direction;
+        }
+        index >>= 1;
+        direction ^= direction >> 1;
+    }
+    result
+}
+
+/// Owen scrambling of the bits of `x`, where each bit is flipped depending on the
+/// bits above it.
+fn nested_uniform_scramble(x: u32, seed: u32) -> u32 {
+    laine_karras_permutation(x.reverse_bits(), seed).reverse_bits()
+}
+
+fn laine_karras_permutation(mut x: u32, seed: u32) -> u32 {
+    x = x.wrapping_add(seed);
+    x ^= x.wrapping_mul(0x6c50_b47c);
+    x ^= x.wrapping_mul(0xb82f_1e52);
+    x ^= x.wrapping_mul(0xc7af_e638);
+    x ^= x.wrapping_mul(0x8d22_f6e6);
+    x
+}
+
+fn to_unit(x: u32) -> f32 {
+    (x >> 8) as f32 / (1 << 24) as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn points(sampler: &dyn Sampler, dimension: usize) -> Vec<Vec2> {
+        (0..16)
+            .map(|sample| {
+                let index = SampleIndex {
+                    seed: 3,
+                    pixel: 10,
+                    sample,
+                    samples_per_pixel: 16,
+                };
+                sampler.get_2d(&index, dimension)
+            })
+            .collect()
+    }
+
+    /// How many points fall in each of `columns` by `rows` cells.
+    fn histogram(points: &[Vec2], columns: usize, rows: usize) -> Vec<usize> {
+        let mut counts = vec![0; columns * rows];
+        for point in points {
+            assert!((0.0..1.0).contains(&point.x) && (0.0..1.0).contains(&point.y));
+            let cell =
+                (point.y * rows as f32) as usize * columns + (point.x * columns as f32) as usize;
+            counts[cell] += 1;
+        }
+        counts
+    }
+
+    #[test]
+    fn test_samples_are_stratified() {
+        for dimension in [0, 5, 40] {
+            for sampler in [&Stratified as &dyn Sampler, &Sobol] {
+                let counts = histogram(&points(sampler, dimension), 4, 4);
+                assert!(counts.iter().all(|&count| count == 1), "{sampler:?}");
+            }
+            for (columns, rows) in [(16, 1), (1, 16)] {
+                let counts = histogram(&points(&Sobol, dimension), columns, rows);
+                assert!(counts.iter().all(|&count| count == 1));
+            }
+        }
+
+        // Base 2 spreads the first coordinate of 16 points one to each sixteenth
+        let counts = histogram(&points(&Halton, 0), 16, 1);
+        assert!(counts.iter().all(|&count| count == 1));
+        assert_ne!(points(&Halton, 0), points(&Halton, 1));
+    }
+}
@@ -1,7 +1,18 @@
-use std::ops::Mul;
+use std::{
+    hash::Hasher,
+    ops::{
+        Add,
+        Mul,
+    },
+};
 
 use glam::Vec3A as Vec3;
 
+use crate::{
+    fingerprint::Fingerprint,
+    tonemap::srgb_oetf,
+};
+
 pub const BLACK: Color = Color::new(0.0, 0.0, 0.0);
 pub const WHITE: Color = Color::new(1.0, 1.0, 1.0);
 pub const RED: Color = Color::new(1.0, 0.0, 0.0);
@@ -36,6 +47,21 @@ impl Color {
         Self((n + Vec3::ONE) * 0.5)
     }
 
+    /// A ramp from blue at 0 through green to red at 1, for showing amounts.
+    pub fn heat(t: f32) -> Self {
+        let t = t.clamp(0.0, 1.0);
+        if t < 0.5 {
+            BLUE.lerp(&GREEN, t * 2.0)
+        } else {
+            GREEN.lerp(&RED, t * 2.0 - 1.0)
+        }
+    }
+
+    pub fn luminance(&self) -> f32 {
+        self.0.dot(Vec3::new(0.2126, 0.7152, 0.0722))
+    }
+
+    /// Encodes the color as 8-bit sRGB, clamping it to the displayable range.
     pub fn bytes(&self) -> [u8; 3] {
         [
             Self::float_to_u8(self.0.x),
@@ -45,7 +71,21 @@ impl Color {
     }
 
     fn float_to_u8(f: f32) -> u8 {
-        (f.sqrt() * 256.0).clamp(0.0, 256.0) as u8
+        (srgb_oetf(f.clamp(0.0, 1.0)) * 255.0).round() as u8
+    }
+}
+
+impl Fingerprint for Color {
+    fn fingerprint(&self, hasher: &mut dyn Hasher) {
+        self.0.fingerprint(hasher);
+    }
+}
+
+impl Add<Color> for Color {
+    type Output = Color;
+
+    fn add(self, rhs: Color) -> Self::Output {
+        Color(self.0 + rhs.0)
     }
 }
 
@@ -57,8 +97,28 @@ impl Mul<Color> for Color {
     }
 }
 
+impl Mul<f32> for Color {
+    type Output = Color;
+
+    fn mul(self, rhs: f32) -> Self::Output {
+        Color(self.0 * rhs)
+    }
+}
+
+impl From<[f32; 3]> for Color {
+    fn from([r, g, b]: [f32; 3]) -> Self {
+        Self::new(r, g, b)
+    }
+}
+
 impl From<&image::Rgb<u8>> for Color {
     fn from(rgb: &image::Rgb<u8>) -> Self {
         Self::new_u8(rgb.0[0], rgb.0[1], rgb.0[2])
     }
 }
+
+impl From<&image::Rgb<f32>> for Color {
+    fn from(rgb: &image::Rgb<f32>) -> Self {
+        Self::new(rgb.0[0], rgb.0[1], rgb.0[2])
+    }
+}
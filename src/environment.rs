@@ -0,0 +1,262 @@
+use std::{
+    f32::consts::PI,
+    fmt,
+    hash::Hasher,
+    path::Path,
+};
+
+use glam::{
+    Mat3A,
+    Vec2,
+    Vec3A as Vec3,
+};
+
+use crate::{
+    color::Color,
+    fingerprint::Fingerprint,
+    hittable::sphere_uv,
+};
+
+/// A piecewise-constant distribution over `[0, 1)`.
+struct Distribution1D {
+    func: Vec<f32>,
+    cdf: Vec<f32>,
+    integral: f32,
+}
+
+impl Distribution1D {
+    fn new(func: Vec<f32>) -> Self {
+        let n = func.len() as f32;
+        let mut cdf = Vec::with_capacity(func.len() + 1);
+        cdf.push(0.0);
+        for f in &func {
+            cdf.push(cdf.last().unwrap() + f / n);
+        }
+        let integral = *cdf.last().unwrap();
+        if integral > 0.0 {
+            cdf.iter_mut().for_each(|c| *c /= integral);
+        } else {
+            // Nothing to importance sample, so fall back to uniform
+            cdf.iter_mut()
+                .enumerate()
+                .for_each(|(i, c)| *c = i as f32 / n);
+        }
+        Self {
+            func,
+            cdf,
+            integral,
+        }
+    }
+
+    /// Returns the sampled position, its pdf and the index of its segment.
+    fn sample(&self, u: f32) -> (f32, f32, usize) {
+        let i = self
+            .cdf
+            .partition_point(|&c| c <= u)
+            .clamp(1, self.func.len())
+            - 1;
+        let width = self.cdf[i + 1] - self.cdf[i];
+        let du = if width > 0.0 {
+            (u - self.cdf[i]) / width
+        } else {
+            0.0
+        };
+        let x = (i as f32 + du) / self.func.len() as f32;
+        (x, self.pdf(i), i)
+    }
+
+    fn pdf(&self, i: usize) -> f32 {
+        if self.integral > 0.0 {
+            self.func[i] / self.integral
+        } else {
+            1.0
+        }
+    }
+}
+
+/// An equirectangular (latitude-longitude) environment light.
+///
+/// Uses the same mapping as `Background::Texture`, so the top row of the image is
+/// straight up and the left edge faces -x.
+pub struct EnvironmentMap {
+    width: usize,
+    height: usize,
+    pixels: Vec<Color>,
+    intensity: f32,
+    to_world: Mat3A,
+    to_local: Mat3A,
+    rows: Vec<Distribution1D>,
+    marginal: Distribution1D,
+}
+
+impl fmt::Debug for EnvironmentMap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EnvironmentMap")
+            .field("width", &self.width)
+            .field("height", &self.height)
+            .field("intensity", &self.intensity)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The sampling tables follow from the pixels.
+impl Fingerprint for EnvironmentMap {
+    fn fingerprint(&self, hasher: &mut dyn Hasher) {
+        self.width.fingerprint(hasher);
+        self.height.fingerprint(hasher);
+        self.pixels.fingerprint(hasher);
+        self.intensity.fingerprint(hasher);
+        self.to_world.fingerprint(hasher);
+    }
+}
+
+impl EnvironmentMap {
+    /// Loads a lat-long image, usually a `.hdr` or `.exr` file with linear radiance.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the image can't be read or decoded.
+    pub fn open(path: impl AsRef<Path>) -> image::ImageResult<Self> {
+        let image = image::open(path)?.to_rgb32f();
+        let pixels = image.pixels().map(Color::from).collect();
+        Ok(Self::new(
+            image.width() as usize,
+            image.height() as usize,
+            pixels,
+        ))
+    }
+
+    pub fn new(width: usize, height: usize, pixels: Vec<Color>) -> Self {
+        assert!(width > 0 && height > 0);
+        assert_eq!(pixels.len(), width * height);
+
+        // Weight by sin(theta) to undo the stretching towards the poles
+        let rows: Vec<_> = (0..height)
+            .map(|y| {
+                let sin_theta = (PI * (y as f32 + 0.5) / height as f32).sin();
+                let func = pixels[y * width..(y + 1) * width]
+                    .iter()
+                    .map(|pixel| pixel.luminance().max(0.0) * sin_theta)
+                    .collect();
+                Distribution1D::new(func)
+            })
+            .collect();
+        let marginal = Distribution1D::new(rows.iter().map(|row| row.integral).collect());
+
+        Self {
+            width,
+            height,
+            pixels,
+            intensity: 1.0,
+            to_world: Mat3A::IDENTITY,
+            to_local: Mat3A::IDENTITY,
+            rows,
+            marginal,
+        }
+    }
+
+    /// Rotates the environment around the vertical axis.
+    pub fn rotation(mut self, degrees: f32) -> Self {
+        self.to_world = Mat3A::from_rotation_y(degrees.to_radians());
+        self.to_local = self.to_world.transpose();
+        self
+    }
+
+    pub fn intensity(mut self, intensity: f32) -> Self {
+        self.intensity = intensity;
+        self
+    }
+
+    pub fn value(&self, direction: Vec3) -> Color {
+        let (x, y) = self.pixel(direction);
+        Color(self.pixels[y * self.width + x].0 * self.intensity)
+    }
+
+    /// Picks a direction with probability proportional to the luminance seen in it.
+    ///
+    /// Returns the unit direction and its pdf with respect to solid angle.
+    pub fn sample(&self, u: Vec2) -> (Vec3, f32) {
+        let (t, _, y) = self.marginal.sample(u.y);
+        let (s, _, x) = self.rows[y].sample(u.x);
+
+        let theta = PI * t;
+        let phi = 2.0 * PI * s;
+        let sin_theta = theta.sin();
+        let local = Vec3::new(-sin_theta * phi.cos(), theta.cos(), sin_theta * phi.sin());
+        let direction = self.to_world * local;
+
+        (direction, self.pdf_pixel(x, y, sin_theta))
+    }
+
+    /// The pdf of `sample` returning `direction`, with respect to solid angle.
+    pub fn pdf(&self, direction: Vec3) -> f32 {
+        let local = (self.to_local * direction).normalize();
+        let sin_theta = (1.0 - local.y * local.y).max(0.0).sqrt();
+        let (x, y) = self.pixel(direction);
+        self.pdf_pixel(x, y, sin_theta)
+    }
+
+    fn pdf_pixel(&self, x: usize, y: usize, sin_theta: f32) -> f32 {
+        if sin_theta <= 0.0 {
+            return 0.0;
+        }
+        let pdf_image = self.marginal.pdf(y) * self.rows[y].pdf(x);
+        pdf_image / (2.0 * PI * PI * sin_theta)
+    }
+
+    fn pixel(&self, direction: Vec3) -> (usize, usize) {
+        let uv = sphere_uv((self.to_local * direction).normalize());
+        let x = ((uv.x * self.width as f32) as usize).min(self.width - 1);
+        let y = (((1.0 - uv.y) * self.height as f32) as usize).min(self.height - 1);
+        (x, y)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        color::{
+            BLACK,
+            WHITE,
+        },
+        rng::{
+            assert_pdf_integrates_to_one,
+            assert_sample_matches_pdf,
+        },
+    };
+
+    fn bright_spot() -> EnvironmentMap {
+        let (width, height) = (16, 8);
+        let mut pixels = vec![Color::new(0.1, 0.1, 0.1); width * height];
+        pixels[2 * width + 5] = Color::new(100.0, 100.0, 100.0);
+        EnvironmentMap::new(width, height, pixels)
+    }
+
+    #[test]
+    fn test_sample_matches_pdf() {
+        let map = bright_spot().rotation(30.0);
+        let sample = |u| {
+            let (direction, pdf) = map.sample(u);
+            assert!((direction.length() - 1.0).abs() < 1e-4);
+            (direction, pdf)
+        };
+        assert_sample_matches_pdf(sample, |direction| map.pdf(direction));
+    }
+
+    #[test]
+    fn test_samples_bright_pixel() {
+        let map = bright_spot();
+        let bright = (0..100)
+            .map(|i| map.sample(Vec2::new(0.5, (i as f32 + 0.5) / 100.0)).0)
+            .filter(|&direction| map.value(direction).0.x > 1.0)
+            .count();
+        assert!(bright > 90);
+    }
+
+    #[test]
+    fn test_pdf_integrates_to_one() {
+        let map = EnvironmentMap::new(2, 2, vec![WHITE, BLACK, BLACK, WHITE]);
+        assert_pdf_integrates_to_one(|direction| map.pdf(direction));
+    }
+}
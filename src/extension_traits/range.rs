@@ -2,12 +2,18 @@ use std::ops::Range;
 
 pub trait RangeExt {
     fn merge(&self, other: &Self) -> Self;
+    fn expand(&self, delta: f32) -> Self;
 }
 
 impl RangeExt for Range<f32> {
     fn merge(&self, other: &Self) -> Self {
         self.start.min(other.start)..self.end.max(other.end)
     }
+
+    fn expand(&self, delta: f32) -> Self {
+        let padding = delta / 2.0;
+        self.start - padding..self.end + padding
+    }
 }
 
 #[cfg(test)]
@@ -37,4 +43,11 @@ mod tests {
         let merged = range1.merge(&range2);
         assert_eq!(merged, 0.0..15.0);
     }
+
+    #[test]
+    fn test_expand() {
+        let range = 1.0..1.0;
+        let expanded = range.expand(1.0);
+        assert_eq!(expanded, 0.5..1.5);
+    }
 }
@@ -1,6 +1,12 @@
-use std::ops::Range;
+use std::{
+    f32::consts::PI,
+    ops::Range,
+};
 
-use glam::Vec3A as Vec3;
+use glam::{
+    Vec2,
+    Vec3A as Vec3,
+};
 
 use crate::rng::random_range;
 
@@ -11,6 +17,11 @@ pub trait Vec3Ext {
     fn random_range(range: &Range<f32>) -> Self;
     fn near_zero(&self) -> bool;
     fn random_in_hemisphere(&self) -> Self;
+    /// Maps a point in the unit square evenly onto the unit disk, keeping nearby points
+    /// together.
+    fn sample_unit_disk(u: Vec2) -> Self;
+    /// Maps a point in the unit square evenly onto the surface of the unit sphere.
+    fn sample_unit_sphere(u: Vec2) -> Self;
     fn refract_custom(&self, normal: Self, etai_over_etat: f32) -> Self;
     fn axis(&self, axis: usize) -> f32;
 }
@@ -27,7 +38,7 @@ impl Vec3Ext for Vec3 {
 
     fn random_unit_vector() -> Self {
         loop {
-            let v = Self::random();
+            let v = Self::random_range(&(-1.0..1.0));
             if (1e-160..=1.0).contains(&v.length_squared()) {
                 return v.normalize();
             }
@@ -60,6 +71,28 @@ impl Vec3Ext for Vec3 {
         }
     }
 
+    fn sample_unit_disk(u: Vec2) -> Self {
+        // Shirley and Chiu's concentric mapping, which turns squares around the center
+        // into circles
+        let offset = u * 2.0 - Vec2::ONE;
+        if offset == Vec2::ZERO {
+            return Self::ZERO;
+        }
+        let (r, theta) = if offset.x.abs() > offset.y.abs() {
+            (offset.x, PI / 4.0 * (offset.y / offset.x))
+        } else {
+            (offset.y, PI / 2.0 - PI / 4.0 * (offset.x / offset.y))
+        };
+        Self::new(r * theta.cos(), r * theta.sin(), 0.0)
+    }
+
+    fn sample_unit_sphere(u: Vec2) -> Self {
+        let z = 1.0 - 2.0 * u.x;
+        let r = (1.0 - z * z).max(0.0).sqrt();
+        let phi = 2.0 * PI * u.y;
+        Self::new(r * phi.cos(), r * phi.sin(), z)
+    }
+
     fn refract_custom(&self, normal: Self, etai_over_etat: f32) -> Self {
         let cos_theta = (-*self).dot(normal).min(1.0);
         let r_out_perp = (*self + normal * cos_theta) * etai_over_etat;
@@ -76,3 +109,20 @@ impl Vec3Ext for Vec3 {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_random_unit_vector_covers_the_sphere() {
+        let n = 10_000;
+        let vectors: Vec<_> = (0..n).map(|_| Vec3::random_unit_vector()).collect();
+        assert!(vectors.iter().all(|v| (v.length() - 1.0).abs() < 1e-4));
+
+        // Uniform directions average out to nothing, with a standard deviation of
+        // about 0.006 per axis
+        let mean = vectors.iter().sum::<Vec3>() / n as f32;
+        assert!(mean.abs().max_element() < 0.05, "{mean}");
+    }
+}
@@ -0,0 +1,121 @@
+use std::{
+    hash::Hasher,
+    sync::Arc,
+};
+
+use glam::{
+    Affine3A,
+    Mat3A,
+    Vec2,
+    Vec3A as Vec3,
+};
+
+/// Feeds everything that changes what a value renders into a hasher, so a checkpoint
+/// can tell whether it is resumed with the same scene.
+///
+/// Trait objects start with their type name, so that different shapes or materials
+/// with the same fields don't collide.
+pub trait Fingerprint {
+    fn fingerprint(&self, hasher: &mut dyn Hasher);
+}
+
+impl Fingerprint for f32 {
+    fn fingerprint(&self, hasher: &mut dyn Hasher) {
+        hasher.write_u32(self.to_bits());
+    }
+}
+
+impl Fingerprint for u32 {
+    fn fingerprint(&self, hasher: &mut dyn Hasher) {
+        hasher.write_u32(*self);
+    }
+}
+
+impl Fingerprint for u64 {
+    fn fingerprint(&self, hasher: &mut dyn Hasher) {
+        hasher.write_u64(*self);
+    }
+}
+
+/// Written as a `u64`, so hashes don't depend on the platform.
+impl Fingerprint for usize {
+    fn fingerprint(&self, hasher: &mut dyn Hasher) {
+        hasher.write_u64(*self as u64);
+    }
+}
+
+impl Fingerprint for str {
+    fn fingerprint(&self, hasher: &mut dyn Hasher) {
+        hasher.write(self.as_bytes());
+        hasher.write_u8(0xff);
+    }
+}
+
+impl Fingerprint for Vec2 {
+    fn fingerprint(&self, hasher: &mut dyn Hasher) {
+        self.to_array().fingerprint(hasher);
+    }
+}
+
+impl Fingerprint for Vec3 {
+    fn fingerprint(&self, hasher: &mut dyn Hasher) {
+        self.to_array().fingerprint(hasher);
+    }
+}
+
+impl Fingerprint for Mat3A {
+    fn fingerprint(&self, hasher: &mut dyn Hasher) {
+        self.to_cols_array().fingerprint(hasher);
+    }
+}
+
+impl Fingerprint for Affine3A {
+    fn fingerprint(&self, hasher: &mut dyn Hasher) {
+        self.to_cols_array().fingerprint(hasher);
+    }
+}
+
+impl<T: Fingerprint> Fingerprint for [T] {
+    fn fingerprint(&self, hasher: &mut dyn Hasher) {
+        self.len().fingerprint(hasher);
+        for value in self {
+            value.fingerprint(hasher);
+        }
+    }
+}
+
+impl<T: Fingerprint, const N: usize> Fingerprint for [T; N] {
+    fn fingerprint(&self, hasher: &mut dyn Hasher) {
+        self.as_slice().fingerprint(hasher);
+    }
+}
+
+impl<T: Fingerprint> Fingerprint for Vec<T> {
+    fn fingerprint(&self, hasher: &mut dyn Hasher) {
+        self.as_slice().fingerprint(hasher);
+    }
+}
+
+impl<T: Fingerprint> Fingerprint for Option<T> {
+    fn fingerprint(&self, hasher: &mut dyn Hasher) {
+        match self {
+            None => hasher.write_u8(0),
+            Some(value) => {
+                hasher.write_u8(1);
+                value.fingerprint(hasher);
+            }
+        }
+    }
+}
+
+impl<T: Fingerprint + ?Sized> Fingerprint for Box<T> {
+    fn fingerprint(&self, hasher: &mut dyn Hasher) {
+        self.as_ref().fingerprint(hasher);
+    }
+}
+
+impl<T: Fingerprint + ?Sized> Fingerprint for Arc<T> {
+    fn fingerprint(&self, hasher: &mut dyn Hasher) {
+        self.as_ref().fingerprint(hasher);
+    }
+}
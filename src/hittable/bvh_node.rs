@@ -1,9 +1,13 @@
-#![allow(dead_code)]
-
-use std::ops::Range;
+use std::{
+    fmt,
+    hash::Hasher,
+    ops::Range,
+};
 
 use crate::{
     aabb::Aabb,
+    extension_traits::Vec3Ext,
+    fingerprint::Fingerprint,
     hittable::{
         HitRecord,
         Hittable,
@@ -12,16 +16,80 @@ use crate::{
     timed_ray::TimedRay,
 };
 
+/// How a `BvhNode` decides where to split its objects.
+#[derive(Debug, Clone, Copy)]
+pub enum SplitStrategy {
+    /// Sorts along the longest axis and splits at the median object.
+    Median,
+    /// Buckets object centroids along the longest axis and picks the split with the
+    /// lowest surface area heuristic cost, stopping once splitting no longer pays off
+    /// for `max_leaf_size` objects or fewer.
+    Sah {
+        buckets: usize,
+        max_leaf_size: usize,
+    },
+}
+
+impl SplitStrategy {
+    pub const SAH: Self = Self::Sah {
+        buckets: 12,
+        max_leaf_size: 4,
+    };
+}
+
 #[derive(Debug)]
-enum Children {
-    One(Box<dyn Hittable>),
-    Two(Box<dyn Hittable>, Box<dyn Hittable>),
+pub(super) enum Children {
+    Leaf(Vec<Box<dyn Hittable>>),
+    Split(Box<BvhNode>, Box<BvhNode>),
+}
+
+impl Children {
+    /// All the objects below, in order.
+    pub(super) fn into_objects(self) -> Vec<Box<dyn Hittable>> {
+        match self {
+            Self::Leaf(objects) => objects,
+            Self::Split(left, right) => {
+                let mut objects = left.children.into_objects();
+                objects.extend(right.children.into_objects());
+                objects
+            }
+        }
+    }
 }
 
 #[derive(Debug)]
 pub struct BvhNode {
-    children: Children,
-    bounding_box: Aabb,
+    pub(super) children: Children,
+    pub(super) bounding_box: Aabb,
+}
+
+/// Measures of how good a BVH is, for comparing construction strategies.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BvhStats {
+    pub interior_nodes: usize,
+    pub leaves: usize,
+    pub objects: usize,
+    pub max_depth: usize,
+    pub max_leaf_size: usize,
+    /// Expected cost of tracing a ray through the tree, counting one for each node
+    /// visited and one for each object tested. Lower is better.
+    pub sah_cost: f32,
+}
+
+impl fmt::Display for BvhStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} interior nodes, {} leaves, {} objects, max depth {}, max leaf size {}, SAH \
+             cost {:.2}",
+            self.interior_nodes,
+            self.leaves,
+            self.objects,
+            self.max_depth,
+            self.max_leaf_size,
+            self.sah_cost
+        )
+    }
 }
 
 impl BvhNode {
@@ -29,58 +97,245 @@ impl BvhNode {
         Self::new(list.objects)
     }
 
-    pub fn new(mut objects: Vec<Box<dyn Hittable>>) -> Self {
-        let (children, bounding_box) = match objects.len() {
-            0 => panic!("No objects to create BVH node from"),
-            1 => {
-                let child = objects.remove(0);
-                let bounding_box = child.bounding_box();
-                (Children::One(child), bounding_box)
+    pub fn from_list_with(list: HittableList, strategy: SplitStrategy) -> Self {
+        Self::with_strategy(list.objects, strategy)
+    }
+
+    pub fn new(objects: Vec<Box<dyn Hittable>>) -> Self {
+        Self::with_strategy(objects, SplitStrategy::Median)
+    }
+
+    pub fn with_strategy(objects: Vec<Box<dyn Hittable>>, strategy: SplitStrategy) -> Self {
+        assert!(!objects.is_empty(), "No objects to create BVH node from");
+        let objects = objects
+            .into_iter()
+            .map(|object| {
+                let bounding_box = object.bounding_box();
+                (object, bounding_box)
+            })
+            .collect();
+        match strategy {
+            SplitStrategy::Median => Self::build_median(objects),
+            SplitStrategy::Sah {
+                buckets,
+                max_leaf_size,
+            } => {
+                assert!(buckets >= 2 && max_leaf_size >= 1);
+                Self::build_sah(objects, buckets, max_leaf_size)
             }
-            2 => {
-                let child1 = objects.remove(0);
-                let child2 = objects.remove(0);
-                let bounding_box = child1.bounding_box().merge(&child2.bounding_box());
-                (Children::Two(child1, child2), bounding_box)
+        }
+    }
+
+    fn leaf(objects: Vec<(Box<dyn Hittable>, Aabb)>) -> Self {
+        let bounding_box = Self::bounds(&objects);
+        Self {
+            children: Children::Leaf(objects.into_iter().map(|(object, _)| object).collect()),
+            bounding_box,
+        }
+    }
+
+    fn split(left: Self, right: Self) -> Self {
+        Self {
+            bounding_box: left.bounding_box.merge(&right.bounding_box),
+            children: Children::Split(Box::new(left), Box::new(right)),
+        }
+    }
+
+    fn bounds(objects: &[(Box<dyn Hittable>, Aabb)]) -> Aabb {
+        objects
+            .iter()
+            .map(|(_, aabb)| aabb.clone())
+            .reduce(|acc, aabb| acc.merge(&aabb))
+            .unwrap()
+    }
+
+    fn build_median(mut objects: Vec<(Box<dyn Hittable>, Aabb)>) -> Self {
+        if objects.len() <= 2 {
+            return Self::leaf(objects);
+        }
+
+        let axis = Self::bounds(&objects).longest_axis();
+        objects
+            .sort_by(|(_, a), (_, b)| a.axis(axis).start.partial_cmp(&b.axis(axis).start).unwrap());
+        let right = objects.split_off(objects.len() / 2);
+        Self::split(Self::build_median(objects), Self::build_median(right))
+    }
+
+    fn build_sah(
+        objects: Vec<(Box<dyn Hittable>, Aabb)>,
+        buckets: usize,
+        max_leaf_size: usize,
+    ) -> Self {
+        if objects.len() == 1 {
+            return Self::leaf(objects);
+        }
+
+        let centroid_bounds = objects
+            .iter()
+            .map(|(_, aabb)| Aabb::new(aabb.centroid(), aabb.centroid()))
+            .reduce(|acc, aabb| acc.merge(&aabb))
+            .unwrap();
+        let axis = centroid_bounds.longest_axis();
+        let range = centroid_bounds.axis(axis);
+        let extent = range.end - range.start;
+
+        if extent <= 0.0 {
+            // Every centroid is in the same place, so no split can separate them
+            if objects.len() <= max_leaf_size {
+                return Self::leaf(objects);
             }
-            _ => {
-                let bounding_box = objects
-                    .iter()
-                    .map(|object| object.bounding_box())
-                    .reduce(|acc, aabb| acc.merge(&aabb))
-                    .unwrap();
-                let comparator = bounding_box.longest_axis_comparator();
-                objects.sort_by(comparator);
-                let mid = objects.len() / 2;
-                let left = Self::new(objects.drain(..mid).collect());
-                let right = Self::new(objects);
-                (Children::Two(Box::new(left), Box::new(right)), bounding_box)
+            return Self::build_median(objects);
+        }
+
+        let bucket = |aabb: &Aabb| {
+            let offset = (aabb.centroid().axis(axis) - range.start) / extent;
+            ((offset * buckets as f32) as usize).min(buckets - 1)
+        };
+
+        let mut counts = vec![0; buckets];
+        let mut bounds: Vec<Option<Aabb>> = vec![None; buckets];
+        for (_, aabb) in &objects {
+            let b = bucket(aabb);
+            counts[b] += 1;
+            bounds[b] = Some(match &bounds[b] {
+                Some(existing) => existing.merge(aabb),
+                None => aabb.clone(),
+            });
+        }
+
+        // Sweep from both ends to get the area and count on each side of every split
+        let sweep = |indices: &mut dyn Iterator<Item = usize>| {
+            let mut area_counts = Vec::with_capacity(buckets - 1);
+            let mut acc: Option<Aabb> = None;
+            let mut count = 0;
+            for i in indices.take(buckets - 1) {
+                if let Some(b) = &bounds[i] {
+                    acc = Some(acc.map_or_else(|| b.clone(), |acc| acc.merge(b)));
+                }
+                count += counts[i];
+                area_counts.push((acc.as_ref().map_or(0.0, Aabb::surface_area), count));
             }
+            area_counts
         };
+        let below = sweep(&mut (0..buckets));
+        let mut above = sweep(&mut (0..buckets).rev());
+        above.reverse();
 
-        Self {
-            children,
-            bounding_box,
+        let total_area = Self::bounds(&objects).surface_area();
+        let (split, cost) = below
+            .iter()
+            .zip(&above)
+            .map(|(&(area_below, count_below), &(area_above, count_above))| {
+                1.0 + (area_below * count_below as f32 + area_above * count_above as f32)
+                    / total_area
+            })
+            .enumerate()
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .unwrap();
+
+        let leaf_cost = objects.len() as f32;
+        if objects.len() <= max_leaf_size && leaf_cost <= cost {
+            return Self::leaf(objects);
+        }
+
+        let (left, right): (Vec<_>, Vec<_>) = objects
+            .into_iter()
+            .partition(|(_, aabb)| bucket(aabb) <= split);
+        if left.is_empty() || right.is_empty() {
+            let objects = if left.is_empty() { right } else { left };
+            return Self::build_median(objects);
+        }
+
+        Self::split(
+            Self::build_sah(left, buckets, max_leaf_size),
+            Self::build_sah(right, buckets, max_leaf_size),
+        )
+    }
+
+    pub fn stats(&self) -> BvhStats {
+        let mut stats = BvhStats::default();
+        self.collect_stats(&mut stats, 1, self.bounding_box.surface_area());
+        stats
+    }
+
+    fn collect_stats(&self, stats: &mut BvhStats, depth: usize, root_area: f32) {
+        let probability = if root_area > 0.0 {
+            self.bounding_box.surface_area() / root_area
+        } else {
+            1.0
+        };
+        stats.max_depth = stats.max_depth.max(depth);
+
+        match &self.children {
+            Children::Leaf(objects) => {
+                stats.leaves += 1;
+                stats.objects += objects.len();
+                stats.max_leaf_size = stats.max_leaf_size.max(objects.len());
+                stats.sah_cost += probability * objects.len() as f32;
+            }
+            Children::Split(left, right) => {
+                stats.interior_nodes += 1;
+                stats.sah_cost += probability;
+                left.collect_stats(stats, depth + 1, root_area);
+                right.collect_stats(stats, depth + 1, root_area);
+            }
+        }
+    }
+}
+
+impl Fingerprint for BvhNode {
+    fn fingerprint(&self, hasher: &mut dyn Hasher) {
+        "BvhNode".fingerprint(hasher);
+        match &self.children {
+            Children::Leaf(objects) => objects.fingerprint(hasher),
+            Children::Split(left, right) => {
+                left.fingerprint(hasher);
+                right.fingerprint(hasher);
+            }
         }
     }
 }
 
 impl Hittable for BvhNode {
     fn hit(&self, r: &TimedRay, interval: &Range<f32>) -> Option<HitRecord> {
+        self.hit_counting(r, interval, &mut 0)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bounding_box.clone()
+    }
+
+    fn hit_counting(
+        &self,
+        r: &TimedRay,
+        interval: &Range<f32>,
+        box_tests: &mut usize,
+    ) -> Option<HitRecord> {
+        *box_tests += 1;
         if !self.bounding_box.hit(r, interval) {
             return None;
         }
 
         match &self.children {
-            Children::One(hittable) => hittable.hit(r, interval),
-            Children::Two(left, right) => {
-                let left_hit = left.hit(r, interval);
+            Children::Leaf(objects) => {
+                let mut output = None;
+                let mut check_interval = interval.clone();
+                for object in objects {
+                    if let Some(hit_record) = object.hit_counting(r, &check_interval, box_tests) {
+                        check_interval = check_interval.start..hit_record.t;
+                        output = Some(hit_record);
+                    }
+                }
+                output
+            }
+            Children::Split(left, right) => {
+                let left_hit = left.hit_counting(r, interval, box_tests);
                 let right_hit = match &left_hit {
                     Some(hit_record) => {
                         let new_interval = interval.start..hit_record.t;
-                        right.hit(r, &new_interval)
+                        right.hit_counting(r, &new_interval, box_tests)
                     }
-                    None => right.hit(r, interval),
+                    None => right.hit_counting(r, interval, box_tests),
                 };
 
                 if right_hit.is_some() {
@@ -91,8 +346,69 @@ impl Hittable for BvhNode {
             }
         }
     }
+}
 
-    fn bounding_box(&self) -> Aabb {
-        self.bounding_box.clone()
+#[cfg(test)]
+mod tests {
+    use glam::Vec3A as Vec3;
+
+    use super::*;
+    use crate::{
+        color::WHITE,
+        hittable::Sphere,
+        material::Metal,
+    };
+
+    fn clustered_spheres() -> Vec<Box<dyn Hittable>> {
+        let mut rng = fastrand::Rng::with_seed(7);
+        (0..500)
+            .map(|i| {
+                // Two dense clusters far apart, which a median split handles badly
+                let offset = if i % 10 == 0 { 100.0 } else { 0.0 };
+                let center = Vec3::new(rng.f32() + offset, rng.f32(), rng.f32() * 50.0);
+                Box::new(Sphere::new_static(center, 0.05, Metal::new(WHITE, 0.0)))
+                    as Box<dyn Hittable>
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_sah_beats_median() {
+        let median = BvhNode::new(clustered_spheres()).stats();
+        let sah = BvhNode::with_strategy(clustered_spheres(), SplitStrategy::SAH).stats();
+        assert_eq!(median.objects, 500);
+        assert_eq!(sah.objects, 500);
+        assert!(sah.max_leaf_size <= 4);
+        assert!(sah.sah_cost < median.sah_cost);
+    }
+
+    #[test]
+    fn test_strategies_agree() {
+        let median = BvhNode::new(clustered_spheres());
+        let sah = BvhNode::with_strategy(clustered_spheres(), SplitStrategy::SAH);
+        let mut rng = fastrand::Rng::with_seed(3);
+        for _ in 0..1000 {
+            let origin = Vec3::new(rng.f32() * 100.0, 5.0, rng.f32() * 50.0);
+            let target = Vec3::new(rng.f32() * 100.0, 0.0, rng.f32() * 50.0);
+            let r = TimedRay::new(origin, target - origin, 0.0);
+            let t_median = median.hit(&r, &(0.0..f32::MAX)).map(|hit| hit.t);
+            let t_sah = sah.hit(&r, &(0.0..f32::MAX)).map(|hit| hit.t);
+            assert_eq!(t_median, t_sah);
+        }
+    }
+
+    #[test]
+    fn test_counts_box_tests() {
+        let bvh = BvhNode::new(clustered_spheres());
+        let down = TimedRay::new(Vec3::new(0.5, 5.0, 25.0), Vec3::new(0.0, -1.0, 0.0), 0.0);
+        let mut box_tests = 0;
+        bvh.hit_counting(&down, &(0.0..f32::MAX), &mut box_tests);
+        assert!(box_tests > 1);
+
+        // Only the root is tested by a ray that misses everything
+        let up = TimedRay::new(down.origin, -down.direction, 0.0);
+        let mut box_tests = 0;
+        bvh.hit_counting(&up, &(0.0..f32::MAX), &mut box_tests);
+        assert_eq!(box_tests, 1);
     }
 }
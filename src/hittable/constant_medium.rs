@@ -0,0 +1,117 @@
+use std::{
+    hash::Hasher,
+    ops::Range,
+};
+
+use glam::{
+    Vec2,
+    Vec3A as Vec3,
+};
+
+use super::Hittable;
+use crate::{
+    aabb::Aabb,
+    fingerprint::Fingerprint,
+    hittable::HitRecord,
+    material::Material,
+    rng,
+    timed_ray::TimedRay,
+};
+
+/// A volume of uniform density filling a closed, convex boundary.
+#[derive(Debug)]
+pub struct ConstantMedium {
+    boundary: Box<dyn Hittable>,
+    neg_inv_density: f32,
+    phase_function: Box<dyn Material>,
+}
+
+impl ConstantMedium {
+    pub fn new(
+        boundary: impl Hittable + 'static,
+        density: f32,
+        phase_function: impl Material + 'static,
+    ) -> Self {
+        assert!(density > 0.0);
+        Self {
+            boundary: Box::new(boundary),
+            neg_inv_density: -1.0 / density,
+            phase_function: Box::new(phase_function),
+        }
+    }
+}
+
+impl Fingerprint for ConstantMedium {
+    fn fingerprint(&self, hasher: &mut dyn Hasher) {
+        "ConstantMedium".fingerprint(hasher);
+        self.boundary.fingerprint(hasher);
+        self.neg_inv_density.fingerprint(hasher);
+        self.phase_function.fingerprint(hasher);
+    }
+}
+
+impl Hittable for ConstantMedium {
+    fn hit(&self, r: &TimedRay, interval: &Range<f32>) -> Option<HitRecord> {
+        // Find where the ray enters and leaves the boundary, even if it starts inside
+        let entry = self.boundary.hit(r, &(f32::MIN..f32::MAX))?.t;
+        let exit = self.boundary.hit(r, &(entry + 0.0001..f32::MAX))?.t;
+
+        let entry = entry.max(interval.start).max(0.0);
+        let exit = exit.min(interval.end);
+        if entry >= exit {
+            return None;
+        }
+
+        let ray_length = r.direction.length();
+        let distance_inside = (exit - entry) * ray_length;
+        // The entry point tells apart the volumes along the same ray
+        let u = fastrand::Rng::with_seed(rng::hash(&[r.key, entry.to_bits().into()])).f32();
+        let hit_distance = self.neg_inv_density * (1.0 - u).ln();
+        if hit_distance > distance_inside {
+            return None;
+        }
+
+        let t = entry + hit_distance / ray_length;
+        Some(HitRecord {
+            point: r.at(t),
+            // A scattering event inside the volume has no surface, so these are arbitrary
+            normal: Vec3::X,
+            uv: Vec2::ZERO,
+            t,
+            front_face: true,
+            in_ray: *r,
+            material: &*self.phase_function,
+        })
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.boundary.bounding_box()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        color::WHITE,
+        hittable::Sphere,
+        material::Metal,
+    };
+
+    #[test]
+    fn test_distances_are_keyed_by_the_ray() {
+        let boundary = Sphere::new_static(Vec3::ZERO, 1000.0, Metal::new(WHITE, 0.0));
+        let medium = ConstantMedium::new(boundary, 0.5, Metal::new(WHITE, 0.0));
+        let r = TimedRay::new(Vec3::ZERO, Vec3::X, 0.0);
+        let distance = |key| {
+            medium
+                .hit(&r.with_key(key), &(0.0..f32::MAX))
+                .map_or(f32::MAX, |hit_record| hit_record.t)
+        };
+        assert_eq!(distance(1).to_bits(), distance(1).to_bits());
+
+        // The mean free path is one over the density
+        let mean = (0..10_000).map(distance).sum::<f32>() / 10_000.0;
+        assert!((mean - 2.0).abs() < 0.1, "{mean}");
+    }
+}
@@ -0,0 +1,151 @@
+use std::{
+    hash::Hasher,
+    ops::Range,
+};
+
+use glam::{
+    Quat,
+    Vec2,
+    Vec3A as Vec3,
+};
+
+use super::{
+    quad::Parallelogram,
+    Hittable,
+};
+use crate::{
+    aabb::Aabb,
+    fingerprint::Fingerprint,
+    hittable::HitRecord,
+    material::Material,
+    timed_ray::TimedRay,
+};
+
+/// A box made of six outward facing quads that share one material.
+#[derive(Debug)]
+pub struct Cuboid {
+    faces: [Parallelogram; 6],
+    material: Box<dyn Material>,
+    bounding_box: Aabb,
+}
+
+impl Cuboid {
+    /// An axis-aligned box with `a` and `b` as opposite corners.
+    pub fn new(a: Vec3, b: Vec3, material: impl Material + 'static) -> Self {
+        let center = (a + b) * 0.5;
+        let half_extents = (b - a).abs() * 0.5;
+        Self::oriented(center, half_extents, Quat::IDENTITY, material)
+    }
+
+    /// A box of size `2 * half_extents` rotated by `rotation` about its center.
+    pub fn oriented(
+        center: Vec3,
+        half_extents: Vec3,
+        rotation: Quat,
+        material: impl Material + 'static,
+    ) -> Self {
+        let x = rotation * Vec3::X * half_extents.x;
+        let y = rotation * Vec3::Y * half_extents.y;
+        let z = rotation * Vec3::Z * half_extents.z;
+
+        let faces = [
+            Parallelogram::new(center - x - y + z, x * 2.0, y * 2.0),
+            Parallelogram::new(center + x - y + z, z * -2.0, y * 2.0),
+            Parallelogram::new(center + x - y - z, x * -2.0, y * 2.0),
+            Parallelogram::new(center - x - y - z, z * 2.0, y * 2.0),
+            Parallelogram::new(center - x + y + z, x * 2.0, z * -2.0),
+            Parallelogram::new(center - x - y - z, x * 2.0, z * 2.0),
+        ];
+        let bounding_box = faces
+            .iter()
+            .map(Parallelogram::bounding_box)
+            .reduce(|acc, aabb| acc.merge(&aabb))
+            .unwrap();
+
+        Self {
+            faces,
+            material: Box::new(material),
+            bounding_box,
+        }
+    }
+}
+
+impl Fingerprint for Cuboid {
+    fn fingerprint(&self, hasher: &mut dyn Hasher) {
+        "Cuboid".fingerprint(hasher);
+        self.faces.fingerprint(hasher);
+        self.material.fingerprint(hasher);
+    }
+}
+
+impl Hittable for Cuboid {
+    fn hit(&self, r: &TimedRay, interval: &Range<f32>) -> Option<HitRecord> {
+        let mut closest = None;
+        let mut check_interval = interval.clone();
+
+        for face in &self.faces {
+            if let Some((t, uv)) = face.intersect(r, &check_interval) {
+                check_interval = check_interval.start..t;
+                closest = Some((t, uv, face.normal));
+            }
+        }
+
+        let (t, uv, outward_normal) = closest?;
+        let (front_face, normal) = HitRecord::front_face(outward_normal, r);
+        Some(HitRecord {
+            point: r.at(t),
+            normal,
+            uv,
+            t,
+            front_face,
+            in_ray: *r,
+            material: &*self.material,
+        })
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bounding_box.clone()
+    }
+
+    /// Samples one of the faces, picked uniformly by `u.x`.
+    fn sample(&self, origin: Vec3, time: f32, u: Vec2) -> Option<(Vec3, f32)> {
+        let (index, u) = super::pick(u, self.faces.len());
+        let (direction, _) = self.faces[index].sample(origin, u);
+        // The direction also crosses the face on the other side
+        Some((direction, self.pdf_value(origin, direction, time)))
+    }
+
+    fn pdf_value(&self, origin: Vec3, direction: Vec3, time: f32) -> f32 {
+        let total: f32 = self
+            .faces
+            .iter()
+            .map(|face| face.pdf_value(origin, direction, time))
+            .sum();
+        total / self.faces.len() as f32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        color::WHITE,
+        material::Metal,
+        rng::{
+            assert_pdf_integrates_to_one,
+            assert_sample_matches_pdf,
+        },
+    };
+
+    #[test]
+    fn test_pdf_integrates_to_one() {
+        let cuboid = Cuboid::new(
+            Vec3::new(-1.0, 1.0, -1.0),
+            Vec3::new(1.0, 2.0, 1.0),
+            Metal::new(WHITE, 0.0),
+        );
+        let pdf = |direction| cuboid.pdf_value(Vec3::ZERO, direction, 0.0);
+        assert_sample_matches_pdf(|u| cuboid.sample(Vec3::ZERO, 0.0, u).unwrap(), pdf);
+        assert_pdf_integrates_to_one(pdf);
+    }
+}
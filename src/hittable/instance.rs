@@ -0,0 +1,131 @@
+use std::{
+    hash::Hasher,
+    ops::Range,
+    sync::Arc,
+};
+
+use glam::{
+    Affine3A,
+    Mat3A,
+    Quat,
+    Vec3A as Vec3,
+};
+
+use super::Hittable;
+use crate::{
+    aabb::Aabb,
+    fingerprint::Fingerprint,
+    hittable::HitRecord,
+    timed_ray::TimedRay,
+};
+
+/// Places a shared object in the world with an affine transform, so one model can be
+/// reused many times without copying it.
+#[derive(Debug)]
+pub struct Instance {
+    object: Arc<dyn Hittable>,
+    to_world: Affine3A,
+    to_object: Affine3A,
+    normal_to_world: Mat3A,
+    bounding_box: Aabb,
+}
+
+impl Instance {
+    pub fn new(object: Arc<dyn Hittable>, transform: Affine3A) -> Self {
+        let to_object = transform.inverse();
+        let bounding_box = object.bounding_box().transform(&transform);
+        Self {
+            object,
+            to_world: transform,
+            to_object,
+            normal_to_world: to_object.matrix3.transpose(),
+            bounding_box,
+        }
+    }
+
+    pub fn translate(self, offset: Vec3) -> Self {
+        self.then(Affine3A::from_translation(offset.into()))
+    }
+
+    pub fn rotate(self, axis: Vec3, degrees: f32) -> Self {
+        let rotation = Quat::from_axis_angle(axis.normalize().into(), degrees.to_radians());
+        self.then(Affine3A::from_quat(rotation))
+    }
+
+    pub fn scale(self, scale: Vec3) -> Self {
+        self.then(Affine3A::from_scale(scale.into()))
+    }
+
+    /// Applies `transform` after the instance's current transform.
+    pub fn then(self, transform: Affine3A) -> Self {
+        Self::new(self.object, transform * self.to_world)
+    }
+}
+
+impl Fingerprint for Instance {
+    fn fingerprint(&self, hasher: &mut dyn Hasher) {
+        "Instance".fingerprint(hasher);
+        self.object.fingerprint(hasher);
+        self.to_world.fingerprint(hasher);
+    }
+}
+
+impl Hittable for Instance {
+    fn hit(&self, r: &TimedRay, interval: &Range<f32>) -> Option<HitRecord> {
+        self.hit_counting(r, interval, &mut 0)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bounding_box.clone()
+    }
+
+    fn hit_counting(
+        &self,
+        r: &TimedRay,
+        interval: &Range<f32>,
+        box_tests: &mut usize,
+    ) -> Option<HitRecord> {
+        // Leave the direction unnormalized so that `t` means the same in both spaces
+        let object_ray = TimedRay {
+            origin: self.to_object.transform_point3a(r.origin),
+            direction: self.to_object.transform_vector3a(r.direction),
+            ..*r
+        };
+        let mut hit_record = self.object.hit_counting(&object_ray, interval, box_tests)?;
+
+        hit_record.point = self.to_world.transform_point3a(hit_record.point);
+        // The inverse transpose keeps normals perpendicular under non-uniform scaling,
+        // and keeps them on the same side of the ray
+        hit_record.normal = (self.normal_to_world * hit_record.normal).normalize();
+        hit_record.in_ray = *r;
+        Some(hit_record)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        color::WHITE,
+        hittable::Quad,
+        material::Metal,
+    };
+
+    #[test]
+    fn test_transformed_hit() {
+        let quad = Quad::new(Vec3::ZERO, Vec3::X, Vec3::Y, Metal::new(WHITE, 0.0));
+        let instance = Instance::new(Arc::new(quad), Affine3A::IDENTITY)
+            .scale(Vec3::new(4.0, 1.0, 1.0))
+            .rotate(Vec3::Y, 90.0)
+            .translate(Vec3::new(0.0, 0.0, 10.0));
+
+        let r = TimedRay::new(Vec3::new(5.0, 0.5, 8.0), Vec3::NEG_X, 0.0);
+        let hit = instance
+            .hit(&r, &(0.0..f32::MAX))
+            .map(|h| (h.t, h.point, h.normal));
+        let (t, point, normal) = hit.unwrap();
+        assert!((t - 5.0).abs() < 1e-5);
+        assert!((point - Vec3::new(0.0, 0.5, 8.0)).length() < 1e-5);
+        assert!((normal - Vec3::X).length() < 1e-5);
+    }
+}
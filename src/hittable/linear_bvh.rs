@@ -0,0 +1,260 @@
+use std::{
+    hash::Hasher,
+    ops::Range,
+};
+
+use glam::Vec3A as Vec3;
+
+use super::{
+    bvh_node::Children,
+    BvhNode,
+    SplitStrategy,
+};
+use crate::{
+    aabb::Aabb,
+    extension_traits::Vec3Ext,
+    fingerprint::Fingerprint,
+    hittable::{
+        HitRecord,
+        Hittable,
+        HittableList,
+    },
+    timed_ray::TimedRay,
+};
+
+/// Deepest tree the fixed-size traversal stack can handle. Deeper subtrees, which
+/// only badly unbalanced splits produce, are flattened into single leaves.
+const MAX_DEPTH: usize = 64;
+
+#[derive(Debug)]
+struct Node {
+    min: Vec3,
+    max: Vec3,
+    /// The first primitive of a leaf, or the second child of an interior node. The
+    /// first child always directly follows its parent.
+    offset: u32,
+    /// Number of primitives, zero for interior nodes.
+    count: u32,
+    /// The axis along which the first child lies before the second.
+    axis: u8,
+}
+
+impl Node {
+    fn hit(&self, origin: Vec3, inv_direction: Vec3, interval: &Range<f32>) -> bool {
+        let t0 = (self.min - origin) * inv_direction;
+        let t1 = (self.max - origin) * inv_direction;
+        let start = t0.min(t1).max_element().max(interval.start);
+        let end = t0.max(t1).min_element().min(interval.end);
+        start < end
+    }
+}
+
+/// A BVH flattened into one contiguous array of nodes in depth-first order.
+///
+/// Traversal uses an explicit stack instead of recursing and visits the child nearer
+/// the ray origin first, so farther children can often be skipped.
+#[derive(Debug)]
+pub struct LinearBvh {
+    nodes: Vec<Node>,
+    primitives: Vec<Box<dyn Hittable>>,
+}
+
+impl LinearBvh {
+    /// Builds with `SplitStrategy::SAH`, whose larger leaves suit the flat layout.
+    pub fn from_list(list: HittableList) -> Self {
+        Self::from(BvhNode::from_list_with(list, SplitStrategy::SAH))
+    }
+
+    pub fn new(objects: Vec<Box<dyn Hittable>>, strategy: SplitStrategy) -> Self {
+        Self::from(BvhNode::with_strategy(objects, strategy))
+    }
+
+    fn flatten(&mut self, node: BvhNode, depth: usize) -> usize {
+        let index = self.nodes.len();
+        let bounding_box = node.bounding_box;
+        self.nodes.push(Node {
+            min: Vec3::from_array([0, 1, 2].map(|axis| bounding_box.axis(axis).start)),
+            max: Vec3::from_array([0, 1, 2].map(|axis| bounding_box.axis(axis).end)),
+            offset: 0,
+            count: 0,
+            axis: 0,
+        });
+
+        let children = match node.children {
+            children @ Children::Split(..) if depth >= MAX_DEPTH => {
+                Children::Leaf(children.into_objects())
+            }
+            children => children,
+        };
+        match children {
+            Children::Leaf(objects) => {
+                self.nodes[index].offset = self.primitives.len() as u32;
+                self.nodes[index].count = objects.len() as u32;
+                self.primitives.extend(objects);
+            }
+            Children::Split(left, right) => {
+                // Order the children along the axis that separates them most
+                let (a, b) = (left.bounding_box.centroid(), right.bounding_box.centroid());
+                let axis = Aabb::new(a, b).longest_axis();
+                let (first, second) = if a.axis(axis) <= b.axis(axis) {
+                    (left, right)
+                } else {
+                    (right, left)
+                };
+                self.flatten(*first, depth + 1);
+                let second = self.flatten(*second, depth + 1);
+                self.nodes[index].offset = second as u32;
+                self.nodes[index].axis = axis as u8;
+            }
+        }
+
+        index
+    }
+}
+
+impl From<BvhNode> for LinearBvh {
+    fn from(node: BvhNode) -> Self {
+        let mut bvh = Self {
+            nodes: Vec::new(),
+            primitives: Vec::new(),
+        };
+        bvh.flatten(node, 0);
+        bvh
+    }
+}
+
+/// The nodes follow from the primitives.
+impl Fingerprint for LinearBvh {
+    fn fingerprint(&self, hasher: &mut dyn Hasher) {
+        "LinearBvh".fingerprint(hasher);
+        self.primitives.fingerprint(hasher);
+    }
+}
+
+impl Hittable for LinearBvh {
+    fn hit(&self, r: &TimedRay, interval: &Range<f32>) -> Option<HitRecord> {
+        self.hit_counting(r, interval, &mut 0)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        let root = &self.nodes[0];
+        Aabb::new(root.min, root.max)
+    }
+
+    fn hit_counting(
+        &self,
+        r: &TimedRay,
+        interval: &Range<f32>,
+        box_tests: &mut usize,
+    ) -> Option<HitRecord> {
+        let inv_direction = r.direction.recip();
+        let negative = [
+            inv_direction.x < 0.0,
+            inv_direction.y < 0.0,
+            inv_direction.z < 0.0,
+        ];
+
+        let mut output = None;
+        let mut check_interval = interval.clone();
+        let mut stack = [0; MAX_DEPTH];
+        let mut stack_len = 0;
+        let mut current = 0;
+
+        loop {
+            let node = &self.nodes[current];
+            *box_tests += 1;
+            if node.hit(r.origin, inv_direction, &check_interval) {
+                if node.count > 0 {
+                    let start = node.offset as usize;
+                    for object in &self.primitives[start..start + node.count as usize] {
+                        if let Some(hit_record) = object.hit_counting(r, &check_interval, box_tests)
+                        {
+                            check_interval = check_interval.start..hit_record.t;
+                            output = Some(hit_record);
+                        }
+                    }
+                } else {
+                    let (near, far) = if negative[node.axis as usize] {
+                        (node.offset as usize, current + 1)
+                    } else {
+                        (current + 1, node.offset as usize)
+                    };
+                    stack[stack_len] = far;
+                    stack_len += 1;
+                    current = near;
+                    continue;
+                }
+            }
+
+            if stack_len == 0 {
+                break;
+            }
+            stack_len -= 1;
+            current = stack[stack_len];
+        }
+
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        color::WHITE,
+        hittable::Sphere,
+        material::Metal,
+    };
+
+    fn random_spheres() -> Vec<Box<dyn Hittable>> {
+        let mut rng = fastrand::Rng::with_seed(11);
+        (0..300)
+            .map(|_| {
+                let center = Vec3::new(rng.f32() * 20.0, rng.f32() * 20.0, rng.f32() * 20.0);
+                Box::new(Sphere::new_static(center, 0.3, Metal::new(WHITE, 0.0)))
+                    as Box<dyn Hittable>
+            })
+            .collect()
+    }
+
+    fn assert_same_hits(expected: &dyn Hittable, actual: &dyn Hittable) {
+        let mut rng = fastrand::Rng::with_seed(5);
+        for _ in 0..2000 {
+            let origin = Vec3::new(rng.f32(), rng.f32(), rng.f32()) * 40.0 - 10.0;
+            let target = Vec3::new(rng.f32(), rng.f32(), rng.f32()) * 20.0;
+            let r = TimedRay::new(origin, target - origin, 0.0);
+            let expected = expected.hit(&r, &(0.001..f32::MAX)).map(|hit| hit.t);
+            let actual = actual.hit(&r, &(0.001..f32::MAX)).map(|hit| hit.t);
+            assert_eq!(expected, actual);
+        }
+    }
+
+    #[test]
+    fn test_matches_bvh_node() {
+        let tree = BvhNode::new(random_spheres());
+        let linear = LinearBvh::new(random_spheres(), SplitStrategy::SAH);
+        assert_same_hits(&tree, &linear);
+    }
+
+    #[test]
+    fn test_deep_tree_is_cut_short() {
+        // Splits that peel off one object at a time make a tree as deep as it is wide
+        let leaf = |object: Box<dyn Hittable>| BvhNode {
+            bounding_box: object.bounding_box(),
+            children: Children::Leaf(vec![object]),
+        };
+        let mut spheres = random_spheres().into_iter();
+        let mut tree = leaf(spheres.next().unwrap());
+        for sphere in spheres {
+            let leaf = leaf(sphere);
+            tree = BvhNode {
+                bounding_box: leaf.bounding_box.merge(&tree.bounding_box),
+                children: Children::Split(Box::new(leaf), Box::new(tree)),
+            };
+        }
+        assert_eq!(tree.stats().max_depth, 300);
+
+        let linear = LinearBvh::from(tree);
+        assert_same_hits(&BvhNode::new(random_spheres()), &linear);
+    }
+}
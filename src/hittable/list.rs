@@ -1,7 +1,16 @@
-use std::ops::Range;
+use std::{
+    hash::Hasher,
+    ops::Range,
+};
+
+use glam::{
+    Vec2,
+    Vec3A as Vec3,
+};
 
 use crate::{
     aabb::Aabb,
+    fingerprint::Fingerprint,
     hittable::{
         HitRecord,
         Hittable,
@@ -22,13 +31,29 @@ impl List {
     }
 }
 
+impl Fingerprint for List {
+    fn fingerprint(&self, hasher: &mut dyn Hasher) {
+        "List".fingerprint(hasher);
+        self.objects.fingerprint(hasher);
+    }
+}
+
 impl Hittable for List {
     fn hit(&self, r: &TimedRay, interval: &Range<f32>) -> Option<HitRecord> {
+        self.hit_counting(r, interval, &mut 0)
+    }
+
+    fn hit_counting(
+        &self,
+        r: &TimedRay,
+        interval: &Range<f32>,
+        box_tests: &mut usize,
+    ) -> Option<HitRecord> {
         let mut output = None;
         let mut check_interval = interval.clone();
 
         for object in &self.objects {
-            if let Some(temp_record) = object.hit(r, &check_interval) {
+            if let Some(temp_record) = object.hit_counting(r, &check_interval, box_tests) {
                 check_interval = check_interval.start..temp_record.t;
                 output = Some(temp_record);
             }
@@ -40,4 +65,28 @@ impl Hittable for List {
     fn bounding_box(&self) -> Aabb {
         self.bounding_box.clone()
     }
+
+    /// Samples one of the objects, picked uniformly by `u.x`, which is then stretched
+    /// back over `[0, 1)` for the object to use.
+    fn sample(&self, origin: Vec3, time: f32, u: Vec2) -> Option<(Vec3, f32)> {
+        if self.objects.is_empty() {
+            return None;
+        }
+        let (index, u) = super::pick(u, self.objects.len());
+        let (direction, _) = self.objects[index].sample(origin, time, u)?;
+        // Another object might lie in the same direction, so sum over all of them
+        Some((direction, self.pdf_value(origin, direction, time)))
+    }
+
+    fn pdf_value(&self, origin: Vec3, direction: Vec3, time: f32) -> f32 {
+        if self.objects.is_empty() {
+            return 0.0;
+        }
+        let total: f32 = self
+            .objects
+            .iter()
+            .map(|object| object.pdf_value(origin, direction, time))
+            .sum();
+        total / self.objects.len() as f32
+    }
 }
@@ -0,0 +1,253 @@
+use std::{
+    fmt,
+    hash::Hasher,
+    ops::Range,
+    sync::Arc,
+};
+
+use glam::{
+    Vec2,
+    Vec3A as Vec3,
+};
+
+use super::{
+    triangle,
+    BvhNode,
+    Hittable,
+    SplitStrategy,
+};
+use crate::{
+    aabb::Aabb,
+    fingerprint::Fingerprint,
+    hittable::HitRecord,
+    material::Material,
+    timed_ray::TimedRay,
+};
+
+struct MeshData {
+    positions: Vec<Vec3>,
+    normals: Option<Vec<Vec3>>,
+    uvs: Option<Vec<Vec2>>,
+    indices: Vec<[u32; 3]>,
+    material: Box<dyn Material>,
+}
+
+impl MeshData {
+    fn vertices(&self, face: usize) -> [Vec3; 3] {
+        self.indices[face].map(|i| self.positions[i as usize])
+    }
+
+    fn normals(&self, face: usize) -> Option<[Vec3; 3]> {
+        let normals = self.normals.as_ref()?;
+        Some(self.indices[face].map(|i| normals[i as usize]))
+    }
+
+    fn uvs(&self, face: usize) -> Option<[Vec2; 3]> {
+        let uvs = self.uvs.as_ref()?;
+        Some(self.indices[face].map(|i| uvs[i as usize]))
+    }
+}
+
+impl fmt::Debug for MeshData {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MeshData")
+            .field("vertices", &self.positions.len())
+            .field("triangles", &self.indices.len())
+            .field("material", &self.material)
+            .finish_non_exhaustive()
+    }
+}
+
+#[derive(Debug)]
+struct MeshTriangle {
+    mesh: Arc<MeshData>,
+    face: usize,
+}
+
+impl Fingerprint for MeshTriangle {
+    fn fingerprint(&self, hasher: &mut dyn Hasher) {
+        "MeshTriangle".fingerprint(hasher);
+        self.mesh.vertices(self.face).fingerprint(hasher);
+        self.mesh.normals(self.face).fingerprint(hasher);
+        self.mesh.uvs(self.face).fingerprint(hasher);
+        self.mesh.material.fingerprint(hasher);
+    }
+}
+
+impl Hittable for MeshTriangle {
+    fn hit(&self, r: &TimedRay, interval: &Range<f32>) -> Option<HitRecord> {
+        let vertices = self.mesh.vertices(self.face);
+        let (t, b1, b2) = triangle::intersect(vertices, r, interval)?;
+        Some(triangle::hit_record(
+            r,
+            t,
+            (b1, b2),
+            vertices,
+            self.mesh.normals(self.face),
+            self.mesh.uvs(self.face),
+            &*self.mesh.material,
+        ))
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        triangle::bounding_box(self.mesh.vertices(self.face))
+    }
+}
+
+/// An indexed triangle mesh with shared vertex buffers and its own BVH.
+///
+/// `normals` and `uvs`, when given, are per-vertex and indexed like `positions`.
+///
+/// Clones share the triangles, so a mesh can be both in the world and among the
+/// lights.
+#[derive(Debug, Clone)]
+pub struct TriangleMesh {
+    bvh: Arc<BvhNode>,
+    mesh: Arc<MeshData>,
+    /// The area of the triangles up to and including each one, for picking them by
+    /// area.
+    areas: Arc<[f32]>,
+}
+
+impl TriangleMesh {
+    pub fn new(
+        positions: Vec<Vec3>,
+        indices: Vec<[u32; 3]>,
+        normals: Option<Vec<Vec3>>,
+        uvs: Option<Vec<Vec2>>,
+        material: impl Material + 'static,
+    ) -> Self {
+        assert!(!indices.is_empty(), "Mesh has no triangles");
+        assert!(indices
+            .iter()
+            .flatten()
+            .all(|&i| (i as usize) < positions.len()));
+        assert!(normals.as_ref().is_none_or(|n| n.len() == positions.len()));
+        assert!(uvs.as_ref().is_none_or(|uv| uv.len() == positions.len()));
+
+        let mesh = Arc::new(MeshData {
+            normals: normals.map(|normals| normals.into_iter().map(Vec3::normalize).collect()),
+            positions,
+            uvs,
+            indices,
+            material: Box::new(material),
+        });
+
+        let triangles = (0..mesh.indices.len())
+            .map(|face| {
+                Box::new(MeshTriangle {
+                    mesh: mesh.clone(),
+                    face,
+                }) as Box<dyn Hittable>
+            })
+            .collect();
+
+        let areas = (0..mesh.indices.len())
+            .scan(0.0, |total, face| {
+                *total += triangle::area(mesh.vertices(face));
+                Some(*total)
+            })
+            .collect();
+
+        Self {
+            bvh: Arc::new(BvhNode::with_strategy(triangles, SplitStrategy::SAH)),
+            mesh,
+            areas,
+        }
+    }
+}
+
+/// The BVH and areas follow from the mesh data.
+impl Fingerprint for TriangleMesh {
+    fn fingerprint(&self, hasher: &mut dyn Hasher) {
+        "TriangleMesh".fingerprint(hasher);
+        self.mesh.positions.fingerprint(hasher);
+        self.mesh.normals.fingerprint(hasher);
+        self.mesh.uvs.fingerprint(hasher);
+        self.mesh.indices.fingerprint(hasher);
+        self.mesh.material.fingerprint(hasher);
+    }
+}
+
+impl Hittable for TriangleMesh {
+    fn hit(&self, r: &TimedRay, interval: &Range<f32>) -> Option<HitRecord> {
+        self.bvh.hit(r, interval)
+    }
+
+    fn hit_counting(
+        &self,
+        r: &TimedRay,
+        interval: &Range<f32>,
+        box_tests: &mut usize,
+    ) -> Option<HitRecord> {
+        self.bvh.hit_counting(r, interval, box_tests)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bvh.bounding_box()
+    }
+
+    /// Picks a triangle by area with `u.x`, which is then stretched back over `[0, 1)`
+    /// to pick the point on it.
+    fn sample(&self, origin: Vec3, time: f32, u: Vec2) -> Option<(Vec3, f32)> {
+        let total = *self.areas.last()?;
+        let target = u.x * total;
+        let face = self
+            .areas
+            .partition_point(|&area| area <= target)
+            .min(self.areas.len() - 1);
+        let start = face
+            .checked_sub(1)
+            .map_or(0.0, |previous| self.areas[previous]);
+        let stretched = ((target - start) / (self.areas[face] - start)).clamp(0.0, 1.0);
+
+        let point = triangle::sample(self.mesh.vertices(face), Vec2::new(stretched, u.y));
+        let direction = point - origin;
+        // The direction may cross the mesh elsewhere too
+        Some((direction, self.pdf_value(origin, direction, time)))
+    }
+
+    /// Tests every triangle, so this is only cheap for small meshes such as lamps.
+    fn pdf_value(&self, origin: Vec3, direction: Vec3, time: f32) -> f32 {
+        let Some(&total) = self.areas.last() else {
+            return 0.0;
+        };
+        let r = TimedRay::new(origin, direction, time);
+        (0..self.mesh.indices.len())
+            .filter_map(|face| {
+                let vertices = self.mesh.vertices(face);
+                let (t, _, _) = triangle::intersect(vertices, &r, &(0.001..f32::MAX))?;
+                Some(triangle::solid_angle_pdf(vertices, direction * t, total))
+            })
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        color::WHITE,
+        material::Metal,
+        rng::{
+            assert_pdf_integrates_to_one,
+            assert_sample_matches_pdf,
+        },
+    };
+
+    #[test]
+    fn test_pdf_integrates_to_one() {
+        // A closed tetrahedron, so directions that hit it cross it twice
+        let positions = vec![
+            Vec3::new(0.0, 1.0, -2.0),
+            Vec3::new(1.0, -1.0, -2.0),
+            Vec3::new(-1.0, -1.0, -2.0),
+            Vec3::new(0.0, 0.0, -4.0),
+        ];
+        let indices = vec![[0, 1, 2], [0, 3, 1], [1, 3, 2], [2, 3, 0]];
+        let mesh = TriangleMesh::new(positions, indices, None, None, Metal::new(WHITE, 0.0));
+        let pdf = |direction| mesh.pdf_value(Vec3::ZERO, direction, 0.0);
+        assert_sample_matches_pdf(|u| mesh.sample(Vec3::ZERO, 0.0, u).unwrap(), pdf);
+        assert_pdf_integrates_to_one(pdf);
+    }
+}
@@ -1,6 +1,7 @@
 use std::{
     fmt::Debug,
     ops::Range,
+    sync::Arc,
 };
 
 use glam::{
@@ -10,15 +11,37 @@ use glam::{
 
 use crate::{
     aabb::Aabb,
+    fingerprint::Fingerprint,
     material::Material,
     timed_ray::TimedRay,
 };
 mod bvh_node;
+mod constant_medium;
+mod cuboid;
+mod instance;
+mod linear_bvh;
 mod list;
+mod mesh;
+mod quad;
 mod sphere;
-pub use bvh_node::BvhNode;
+mod triangle;
+pub use bvh_node::{
+    BvhNode,
+    BvhStats,
+    SplitStrategy,
+};
+pub use constant_medium::ConstantMedium;
+pub use cuboid::Cuboid;
+pub use instance::Instance;
+pub use linear_bvh::LinearBvh;
 pub use list::List as HittableList;
-pub use sphere::Sphere;
+pub use mesh::TriangleMesh;
+pub use quad::Quad;
+pub use sphere::{
+    sphere_uv,
+    Sphere,
+};
+pub use triangle::Triangle;
 pub struct HitRecord<'a> {
     pub point: Vec3,
     pub uv: Vec2,
@@ -37,7 +60,68 @@ impl HitRecord<'_> {
     }
 }
 
-pub trait Hittable: Sync + Debug {
+pub trait Hittable: Send + Sync + Debug + Fingerprint {
     fn hit(&self, r: &TimedRay, interval: &Range<f32>) -> Option<HitRecord>;
     fn bounding_box(&self) -> Aabb;
+
+    /// Like `hit`, also adding the bounding boxes tested on the way to `box_tests`.
+    fn hit_counting(
+        &self,
+        r: &TimedRay,
+        interval: &Range<f32>,
+        _box_tests: &mut usize,
+    ) -> Option<HitRecord> {
+        self.hit(r, interval)
+    }
+
+    /// Picks a point on the surface as seen from `origin`, for sampling it as a light,
+    /// placed by `u` in `[0, 1)²`.
+    ///
+    /// Returns the direction from `origin` to the point and the pdf of choosing that
+    /// direction with respect to solid angle, or `None` if the shape can't be sampled.
+    fn sample(&self, _origin: Vec3, _time: f32, _u: Vec2) -> Option<(Vec3, f32)> {
+        None
+    }
+
+    /// The pdf with respect to solid angle of `sample` returning `direction`.
+    fn pdf_value(&self, _origin: Vec3, _direction: Vec3, _time: f32) -> f32 {
+        0.0
+    }
+}
+
+/// Lets the world and the lights share an emitter.
+impl<T: Hittable + ?Sized> Hittable for Arc<T> {
+    fn hit(&self, r: &TimedRay, interval: &Range<f32>) -> Option<HitRecord> {
+        self.as_ref().hit(r, interval)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.as_ref().bounding_box()
+    }
+
+    fn hit_counting(
+        &self,
+        r: &TimedRay,
+        interval: &Range<f32>,
+        box_tests: &mut usize,
+    ) -> Option<HitRecord> {
+        self.as_ref().hit_counting(r, interval, box_tests)
+    }
+
+    fn sample(&self, origin: Vec3, time: f32, u: Vec2) -> Option<(Vec3, f32)> {
+        self.as_ref().sample(origin, time, u)
+    }
+
+    fn pdf_value(&self, origin: Vec3, direction: Vec3, time: f32) -> f32 {
+        self.as_ref().pdf_value(origin, direction, time)
+    }
+}
+
+/// Picks one of `count` parts uniformly by `u.x`, which is then stretched back over
+/// `[0, 1)` for the part to use.
+fn pick(mut u: Vec2, count: usize) -> (usize, Vec2) {
+    let scaled = u.x * count as f32;
+    let index = (scaled as usize).min(count - 1);
+    u.x = (scaled - index as f32).min(1.0 - f32::EPSILON / 2.0);
+    (index, u)
 }
@@ -0,0 +1,189 @@
+use std::{
+    hash::Hasher,
+    ops::Range,
+};
+
+use glam::{
+    Vec2,
+    Vec3A as Vec3,
+};
+
+use super::Hittable;
+use crate::{
+    aabb::Aabb,
+    fingerprint::Fingerprint,
+    hittable::HitRecord,
+    material::Material,
+    timed_ray::TimedRay,
+};
+
+/// The geometry of a quad, spanned by `u` and `v` from the corner `q`.
+#[derive(Debug, Clone)]
+pub(super) struct Parallelogram {
+    q: Vec3,
+    u: Vec3,
+    v: Vec3,
+    w: Vec3,
+    pub(super) normal: Vec3,
+    d: f32,
+    area: f32,
+}
+
+impl Parallelogram {
+    pub(super) fn new(q: Vec3, u: Vec3, v: Vec3) -> Self {
+        let n = u.cross(v);
+        assert!(n.length_squared() > 0.0, "Quad edges must not be parallel");
+        let normal = n.normalize();
+        Self {
+            q,
+            u,
+            v,
+            w: n / n.dot(n),
+            normal,
+            d: normal.dot(q),
+            area: n.length(),
+        }
+    }
+
+    pub(super) fn bounding_box(&self) -> Aabb {
+        let diagonal1 = Aabb::new(self.q, self.q + self.u + self.v);
+        let diagonal2 = Aabb::new(self.q + self.u, self.q + self.v);
+        diagonal1.merge(&diagonal2).pad(0.0001)
+    }
+
+    /// Returns `t` and the position of the hit in the `u`, `v` frame.
+    pub(super) fn intersect(&self, r: &TimedRay, interval: &Range<f32>) -> Option<(f32, Vec2)> {
+        let denom = self.normal.dot(r.direction);
+        if denom.abs() < 1e-8 {
+            return None;
+        }
+
+        let t = (self.d - self.normal.dot(r.origin)) / denom;
+        if t <= interval.start || t >= interval.end {
+            return None;
+        }
+
+        let planar = r.at(t) - self.q;
+        let alpha = self.w.dot(planar.cross(self.v));
+        let beta = self.w.dot(self.u.cross(planar));
+        if !(0.0..=1.0).contains(&alpha) || !(0.0..=1.0).contains(&beta) {
+            return None;
+        }
+
+        Some((t, Vec2::new(alpha, beta)))
+    }
+
+    /// Picks the point at `u` across the area.
+    pub(super) fn sample(&self, origin: Vec3, u: Vec2) -> (Vec3, f32) {
+        let point = self.q + self.u * u.x + self.v * u.y;
+        let direction = point - origin;
+        (direction, self.solid_angle_pdf(direction))
+    }
+
+    pub(super) fn pdf_value(&self, origin: Vec3, direction: Vec3, time: f32) -> f32 {
+        let r = TimedRay::new(origin, direction, time);
+        match self.intersect(&r, &(0.001..f32::MAX)) {
+            Some((t, _)) => self.solid_angle_pdf(direction * t),
+            None => 0.0,
+        }
+    }
+
+    /// Converts the area pdf to solid angle for the point `direction` away.
+    fn solid_angle_pdf(&self, direction: Vec3) -> f32 {
+        let distance_squared = direction.length_squared();
+        let cosine = self.normal.dot(direction).abs() / distance_squared.sqrt();
+        if cosine < 1e-8 {
+            return 0.0;
+        }
+        distance_squared / (cosine * self.area)
+    }
+}
+
+#[derive(Debug)]
+pub struct Quad {
+    shape: Parallelogram,
+    material: Box<dyn Material>,
+    bounding_box: Aabb,
+}
+
+impl Quad {
+    pub fn new(q: Vec3, u: Vec3, v: Vec3, material: impl Material + 'static) -> Self {
+        let shape = Parallelogram::new(q, u, v);
+        Self {
+            bounding_box: shape.bounding_box(),
+            shape,
+            material: Box::new(material),
+        }
+    }
+}
+
+impl Fingerprint for Parallelogram {
+    fn fingerprint(&self, hasher: &mut dyn Hasher) {
+        self.q.fingerprint(hasher);
+        self.u.fingerprint(hasher);
+        self.v.fingerprint(hasher);
+    }
+}
+
+impl Fingerprint for Quad {
+    fn fingerprint(&self, hasher: &mut dyn Hasher) {
+        "Quad".fingerprint(hasher);
+        self.shape.fingerprint(hasher);
+        self.material.fingerprint(hasher);
+    }
+}
+
+impl Hittable for Quad {
+    fn hit(&self, r: &TimedRay, interval: &Range<f32>) -> Option<HitRecord> {
+        let (t, uv) = self.shape.intersect(r, interval)?;
+        let (front_face, normal) = HitRecord::front_face(self.shape.normal, r);
+
+        Some(HitRecord {
+            point: r.at(t),
+            normal,
+            uv,
+            t,
+            front_face,
+            in_ray: *r,
+            material: &*self.material,
+        })
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bounding_box.clone()
+    }
+
+    fn sample(&self, origin: Vec3, _time: f32, u: Vec2) -> Option<(Vec3, f32)> {
+        Some(self.shape.sample(origin, u))
+    }
+
+    fn pdf_value(&self, origin: Vec3, direction: Vec3, time: f32) -> f32 {
+        self.shape.pdf_value(origin, direction, time)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        color::WHITE,
+        material::Metal,
+        rng::{
+            assert_pdf_integrates_to_one,
+            assert_sample_matches_pdf,
+        },
+    };
+
+    #[test]
+    fn test_pdf_integrates_to_one() {
+        let quad = Quad::new(
+            Vec3::new(-1.0, 1.0, -1.0),
+            Vec3::new(2.0, 0.0, 0.0),
+            Vec3::new(0.0, 0.0, 2.0),
+            Metal::new(WHITE, 0.0),
+        );
+        let pdf = |direction| quad.pdf_value(Vec3::ZERO, direction, 0.0);
+        assert_sample_matches_pdf(|u| quad.sample(Vec3::ZERO, 0.0, u).unwrap(), pdf);
+        assert_pdf_integrates_to_one(pdf);
+    }
+}
@@ -1,5 +1,6 @@
 use std::{
     f32::consts::PI,
+    hash::Hasher,
     ops::Range,
 };
 
@@ -11,6 +12,7 @@ use glam::{
 use super::Hittable;
 use crate::{
     aabb::Aabb,
+    fingerprint::Fingerprint,
     hittable::HitRecord,
     material::Material,
     ray::Ray,
@@ -55,6 +57,15 @@ impl Sphere {
     }
 }
 
+impl Fingerprint for Sphere {
+    fn fingerprint(&self, hasher: &mut dyn Hasher) {
+        "Sphere".fingerprint(hasher);
+        self.center.fingerprint(hasher);
+        self.radius.fingerprint(hasher);
+        self.material.fingerprint(hasher);
+    }
+}
+
 impl Hittable for Sphere {
     fn hit(&self, r: &TimedRay, interval: &Range<f32>) -> Option<HitRecord> {
         let center = self.center.at(r.time);
@@ -86,15 +97,10 @@ impl Hittable for Sphere {
         let outward_normal = (point - center) / self.radius;
         let (front_face, normal) = HitRecord::front_face(outward_normal, r);
 
-        let phi = (-outward_normal.z).atan2(outward_normal.x) + PI;
-        let theta = (-outward_normal.y).acos();
-        let u = phi / (2.0 * PI);
-        let v = theta / PI;
-
         Some(HitRecord {
             point,
             normal,
-            uv: Vec2::new(u, v),
+            uv: sphere_uv(outward_normal),
             t,
             front_face,
             in_ray: *r,
@@ -105,4 +111,83 @@ impl Hittable for Sphere {
     fn bounding_box(&self) -> Aabb {
         self.bounding_box.clone()
     }
+
+    /// Samples the cone of directions the sphere covers, which is only possible from
+    /// outside it.
+    fn sample(&self, origin: Vec3, time: f32, u: Vec2) -> Option<(Vec3, f32)> {
+        let to_center = self.center.at(time) - origin;
+        let cos_theta_max = self.cos_theta_max(to_center)?;
+
+        let cos_theta = 1.0 + u.x * (cos_theta_max - 1.0);
+        let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+        let phi = 2.0 * PI * u.y;
+        let w = to_center.normalize();
+        let (u, v) = w.any_orthonormal_pair();
+        let direction = u * (phi.cos() * sin_theta) + v * (phi.sin() * sin_theta) + w * cos_theta;
+
+        Some((direction, Self::cone_pdf(cos_theta_max)))
+    }
+
+    fn pdf_value(&self, origin: Vec3, direction: Vec3, time: f32) -> f32 {
+        let to_center = self.center.at(time) - origin;
+        match self.cos_theta_max(to_center) {
+            Some(cos_theta_max)
+                if direction.normalize().dot(to_center.normalize()) >= cos_theta_max =>
+            {
+                Self::cone_pdf(cos_theta_max)
+            }
+            _ => 0.0,
+        }
+    }
+}
+
+impl Sphere {
+    /// The cosine of the half angle of the cone the sphere covers, seen from
+    /// `to_center` away, or `None` from inside.
+    fn cos_theta_max(&self, to_center: Vec3) -> Option<f32> {
+        let distance_squared = to_center.length_squared();
+        let radius_squared = self.radius * self.radius;
+        (distance_squared > radius_squared)
+            .then(|| (1.0 - radius_squared / distance_squared).sqrt())
+    }
+
+    fn cone_pdf(cos_theta_max: f32) -> f32 {
+        1.0 / (2.0 * PI * (1.0 - cos_theta_max))
+    }
+}
+
+/// Texture coordinates of a point on the unit sphere, with `v` running from the bottom
+/// to the top and `u` wrapping around from -x.
+pub fn sphere_uv(p: Vec3) -> Vec2 {
+    let phi = (-p.z).atan2(p.x) + PI;
+    let theta = (-p.y).acos();
+    Vec2::new(phi / (2.0 * PI), theta / PI)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        color::WHITE,
+        material::Metal,
+        rng::{
+            assert_pdf_integrates_to_one,
+            assert_sample_matches_pdf,
+        },
+    };
+
+    #[test]
+    fn test_sample_matches_pdf() {
+        let sphere = Sphere::new_static(Vec3::new(0.0, 0.0, -2.0), 1.0, Metal::new(WHITE, 0.0));
+        let origin = Vec3::new(0.5, 0.0, 0.0);
+        let pdf = |direction| sphere.pdf_value(origin, direction, 0.0);
+        let sample = |u| {
+            let (direction, pdf) = sphere.sample(origin, 0.0, u).unwrap();
+            let r = TimedRay::new(origin, direction, 0.0);
+            assert!(sphere.hit(&r, &(0.001..f32::MAX)).is_some());
+            (direction, pdf)
+        };
+        assert_sample_matches_pdf(sample, pdf);
+        assert_pdf_integrates_to_one(pdf);
+    }
 }
@@ -0,0 +1,224 @@
+use std::{
+    hash::Hasher,
+    ops::Range,
+};
+
+use glam::{
+    Vec2,
+    Vec3A as Vec3,
+};
+
+use super::Hittable;
+use crate::{
+    aabb::Aabb,
+    fingerprint::Fingerprint,
+    hittable::HitRecord,
+    material::Material,
+    timed_ray::TimedRay,
+};
+
+#[derive(Debug)]
+pub struct Triangle {
+    vertices: [Vec3; 3],
+    normals: Option<[Vec3; 3]>,
+    uvs: Option<[Vec2; 3]>,
+    material: Box<dyn Material>,
+    bounding_box: Aabb,
+}
+
+impl Triangle {
+    pub fn new(a: Vec3, b: Vec3, c: Vec3, material: impl Material + 'static) -> Self {
+        let vertices = [a, b, c];
+        Self {
+            vertices,
+            normals: None,
+            uvs: None,
+            material: Box::new(material),
+            bounding_box: bounding_box(vertices),
+        }
+    }
+
+    pub fn normals(mut self, normals: [Vec3; 3]) -> Self {
+        self.normals = Some(normals.map(Vec3::normalize));
+        self
+    }
+
+    pub fn uvs(mut self, uvs: [Vec2; 3]) -> Self {
+        self.uvs = Some(uvs);
+        self
+    }
+}
+
+impl Fingerprint for Triangle {
+    fn fingerprint(&self, hasher: &mut dyn Hasher) {
+        "Triangle".fingerprint(hasher);
+        self.vertices.fingerprint(hasher);
+        self.normals.fingerprint(hasher);
+        self.uvs.fingerprint(hasher);
+        self.material.fingerprint(hasher);
+    }
+}
+
+impl Hittable for Triangle {
+    fn hit(&self, r: &TimedRay, interval: &Range<f32>) -> Option<HitRecord> {
+        let (t, b1, b2) = intersect(self.vertices, r, interval)?;
+        Some(hit_record(
+            r,
+            t,
+            (b1, b2),
+            self.vertices,
+            self.normals,
+            self.uvs,
+            &*self.material,
+        ))
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bounding_box.clone()
+    }
+}
+
+pub(super) fn bounding_box([a, b, c]: [Vec3; 3]) -> Aabb {
+    Aabb::new(a.min(b).min(c), a.max(b).max(c)).pad(0.0001)
+}
+
+/// Möller–Trumbore intersection, returning `t` and the barycentric coordinates of
+/// the second and third vertices.
+pub(super) fn intersect(
+    [v0, v1, v2]: [Vec3; 3],
+    r: &TimedRay,
+    interval: &Range<f32>,
+) -> Option<(f32, f32, f32)> {
+    let edge1 = v1 - v0;
+    let edge2 = v2 - v0;
+    let p = r.direction.cross(edge2);
+    let det = edge1.dot(p);
+    if det.abs() < 1e-12 {
+        return None;
+    }
+    let inv_det = 1.0 / det;
+
+    let s = r.origin - v0;
+    let b1 = s.dot(p) * inv_det;
+    if !(0.0..=1.0).contains(&b1) {
+        return None;
+    }
+
+    let q = s.cross(edge1);
+    let b2 = r.direction.dot(q) * inv_det;
+    if b2 < 0.0 || b1 + b2 > 1.0 {
+        return None;
+    }
+
+    let t = edge2.dot(q) * inv_det;
+    if t <= interval.start || t >= interval.end {
+        return None;
+    }
+
+    Some((t, b1, b2))
+}
+
+pub(super) fn area([v0, v1, v2]: [Vec3; 3]) -> f32 {
+    0.5 * (v1 - v0).cross(v2 - v0).length()
+}
+
+/// The point at `u` with points spread evenly over the triangle.
+pub(super) fn sample([v0, v1, v2]: [Vec3; 3], u: Vec2) -> Vec3 {
+    let s = u.x.sqrt();
+    v0 * (1.0 - s) + v1 * (s * (1.0 - u.y)) + v2 * (s * u.y)
+}
+
+/// Converts a pdf of one over `area` to solid angle, for the triangle's point
+/// `direction` away.
+pub(super) fn solid_angle_pdf([v0, v1, v2]: [Vec3; 3], direction: Vec3, area: f32) -> f32 {
+    let normal = (v1 - v0).cross(v2 - v0).normalize();
+    let distance_squared = direction.length_squared();
+    let cosine = normal.dot(direction).abs() / distance_squared.sqrt();
+    if cosine < 1e-8 {
+        return 0.0;
+    }
+    distance_squared / (cosine * area)
+}
+
+pub(super) fn hit_record<'a>(
+    r: &TimedRay,
+    t: f32,
+    (b1, b2): (f32, f32),
+    [v0, v1, v2]: [Vec3; 3],
+    normals: Option<[Vec3; 3]>,
+    uvs: Option<[Vec2; 3]>,
+    material: &'a dyn Material,
+) -> HitRecord<'a> {
+    let b0 = 1.0 - b1 - b2;
+    let geometric_normal = (v1 - v0).cross(v2 - v0).normalize();
+    let (front_face, _) = HitRecord::front_face(geometric_normal, r);
+
+    let outward_normal = match normals {
+        Some([n0, n1, n2]) => (n0 * b0 + n1 * b1 + n2 * b2).normalize(),
+        None => geometric_normal,
+    };
+    let normal = if front_face {
+        outward_normal
+    } else {
+        -outward_normal
+    };
+
+    let uv = match uvs {
+        Some([t0, t1, t2]) => t0 * b0 + t1 * b1 + t2 * b2,
+        None => Vec2::new(b1, b2),
+    };
+
+    HitRecord {
+        point: r.at(t),
+        uv,
+        normal,
+        t,
+        front_face,
+        in_ray: *r,
+        material,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        color::WHITE,
+        material::Metal,
+    };
+
+    fn triangle() -> Triangle {
+        Triangle::new(
+            Vec3::new(0.0, 0.0, 0.0),
+            Vec3::new(1.0, 0.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            Metal::new(WHITE, 0.0),
+        )
+    }
+
+    #[test]
+    fn test_hit() {
+        let r = TimedRay::new(Vec3::new(0.25, 0.5, 1.0), Vec3::NEG_Z, 0.0);
+        let hit = triangle().hit(&r, &(0.0..f32::MAX)).map(|h| (h.t, h.uv));
+        let (t, uv) = hit.unwrap();
+        assert!((t - 1.0).abs() < 1e-6);
+        assert!((uv - Vec2::new(0.25, 0.5)).length() < 1e-6);
+    }
+
+    #[test]
+    fn test_miss() {
+        let r = TimedRay::new(Vec3::new(0.75, 0.75, 1.0), Vec3::NEG_Z, 0.0);
+        assert!(triangle().hit(&r, &(0.0..f32::MAX)).is_none());
+    }
+
+    #[test]
+    fn test_back_face() {
+        let r = TimedRay::new(Vec3::new(0.25, 0.25, -1.0), Vec3::Z, 0.0);
+        let hit = triangle()
+            .hit(&r, &(0.0..f32::MAX))
+            .map(|h| (h.front_face, h.normal));
+        let (front_face, normal) = hit.unwrap();
+        assert!(!front_face);
+        assert_eq!(normal, Vec3::NEG_Z);
+    }
+}
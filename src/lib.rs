@@ -10,10 +10,16 @@
 mod aabb;
 pub mod camera;
 pub mod color;
+pub mod environment;
 pub mod extension_traits;
+pub mod fingerprint;
 pub mod hittable;
 pub mod material;
+pub mod obj;
+pub mod output;
 mod ray;
 pub mod rng;
+pub mod scene_file;
 pub mod texture;
 mod timed_ray;
+pub mod tonemap;
@@ -1,7 +1,45 @@
+use std::{
+    fmt::Display,
+    ops::ControlFlow,
+    path::{
+        Path,
+        PathBuf,
+    },
+    process,
+    time::Duration,
+};
+
 use clap::{
     Parser,
     ValueEnum,
 };
+use ray_tracing::{
+    camera::{
+        self,
+        Adaptive,
+        Albedo,
+        Camera,
+        Checkpoint,
+        Depth,
+        Film,
+        Halton,
+        Heatmap,
+        Independent,
+        Normals,
+        PathTracer,
+        Sobol,
+        Stratified,
+        Uv,
+    },
+    output::{
+        self,
+        Format,
+    },
+    tonemap::{
+        self,
+        ToneMap,
+    },
+};
 mod scenes;
 
 #[allow(clippy::enum_variant_names)]
@@ -12,6 +50,53 @@ enum Scene {
     ManyBouncingSpheres,
     CheckerSpheres,
     Globe,
+    Quads,
+    Mesh,
+    Cube,
+    SimpleLight,
+    CornellBox,
+    Instances,
+    CornellSmoke,
+}
+
+#[derive(ValueEnum, Clone, Default)]
+enum Integrator {
+    #[default]
+    Path,
+    Normals,
+    Depth,
+    Uv,
+    Albedo,
+    Heatmap,
+}
+
+#[derive(ValueEnum, Clone, Default)]
+enum Sampler {
+    #[default]
+    Independent,
+    Stratified,
+    Halton,
+    Sobol,
+}
+
+#[derive(ValueEnum, Clone, Default)]
+enum Filter {
+    #[default]
+    Box,
+    Tent,
+    Gaussian,
+    Mitchell,
+    Lanczos,
+}
+
+#[derive(ValueEnum, Clone, Default)]
+enum Operator {
+    #[default]
+    Clamp,
+    Reinhard,
+    ExtendedReinhard,
+    Aces,
+    Agx,
 }
 
 #[derive(Parser)]
@@ -22,19 +107,282 @@ struct Args {
     /// The scene to render
     #[arg(short, long, default_value = "many-spheres")]
     scene: Scene,
+    /// Render a TOML scene file instead of a built-in scene
+    #[arg(long, conflicts_with = "scene")]
+    scene_file: Option<PathBuf>,
+    /// Let Russian roulette end paths after this many bounces
+    #[arg(long, value_name = "MIN_DEPTH")]
+    russian_roulette: Option<usize>,
+    /// How to turn rays into colors, either full lighting or a debug view
+    #[arg(short, long, default_value = "path")]
+    integrator: Integrator,
+    /// Spend the samples per pixel where the noise is, stopping each pixel once the
+    /// error of its mean is below this fraction of it
+    #[arg(long, value_name = "THRESHOLD")]
+    adaptive: Option<f32>,
+    /// The fewest samples a pixel takes with --adaptive
+    #[arg(long, default_value_t = 16, requires = "adaptive")]
+    min_samples: usize,
+    /// The most samples a pixel takes with --adaptive
+    #[arg(long, default_value_t = 1024, requires = "adaptive")]
+    max_samples: usize,
+    /// Also save an image of how many samples each pixel took
+    #[arg(long, value_name = "PATH")]
+    sample_heatmap: Option<PathBuf>,
+    /// How to spread samples over pixels, the lens, time and bounces
+    #[arg(long, default_value = "independent")]
+    sampler: Sampler,
+    /// How samples are weighted into nearby pixels
+    #[arg(long, default_value = "box")]
+    filter: Filter,
+    /// How far samples reach, in pixels. Defaults to 0.5 for box, 1 for tent, 1.5 for
+    /// gaussian and 2 for mitchell and lanczos
+    #[arg(long)]
+    filter_radius: Option<f32>,
+    /// Stops to brighten or darken the image by before tone mapping
+    #[arg(long, default_value_t = 0.0, allow_hyphen_values = true)]
+    exposure: f32,
+    /// How to fit bright light into 8-bit images
+    #[arg(long, default_value = "clamp")]
+    tone_map: Operator,
+    /// The radiance that extended Reinhard maps to white
+    #[arg(long, default_value_t = 4.0)]
+    white_point: f32,
+    /// Seed for all randomness, including scene generation, so renders can be
+    /// reproduced exactly
+    #[arg(long)]
+    seed: Option<u64>,
+    /// Where to save the image
+    #[arg(short, long, default_value = "last_run.png")]
+    output: PathBuf,
+    /// The image format, if it can't be told from the output file's extension. Use
+    /// exr-half for 16-bit EXR
+    #[arg(long)]
+    format: Option<Format>,
+    /// Render in passes of doubling samples per pixel, saving the image after each
+    #[arg(long)]
+    progressive: bool,
+    /// Also keep a timestamped copy of the image in the out directory
+    #[arg(long)]
+    archive: bool,
+    /// Save the render's progress here after every pass and every
+    /// --checkpoint-interval seconds
+    #[arg(long)]
+    checkpoint: Option<PathBuf>,
+    /// Seconds between checkpoints within a pass
+    #[arg(long, default_value_t = 300)]
+    checkpoint_interval: u64,
+    /// Carry on with the render saved in --checkpoint, which needs the same scene and
+    /// options it was started with
+    #[arg(long, requires = "checkpoint")]
+    resume: bool,
 }
 
-fn main() {
-    let args = Args::parse();
-    let mut builder = match args.scene {
-        Scene::ManySpheres => scenes::many_spheres(),
-        Scene::ManyBouncingSpheres => scenes::many_bouncing_spheres(),
-        Scene::CheckerSpheres => scenes::checkered_spheres(),
-        Scene::Globe => scenes::world(),
+fn exit_with_error(message: impl Display) -> ! {
+    eprintln!("{message}");
+    process::exit(1);
+}
+
+/// The seed to build the scene and render with. Renders that may be resumed always
+/// get one, since resuming has to generate the same scene again.
+fn choose_seed(args: &Args, checkpoint_seed: Option<u64>) -> Option<u64> {
+    match (checkpoint_seed, args.seed) {
+        (Some(checkpoint_seed), Some(seed)) if seed != checkpoint_seed => exit_with_error(format!(
+            "the checkpoint was rendered with --seed {checkpoint_seed}"
+        )),
+        (Some(checkpoint_seed), _) => Some(checkpoint_seed),
+        (None, None) if args.checkpoint.is_some() => Some(fastrand::u64(..)),
+        (None, seed) => seed,
+    }
+}
+
+/// Builds the scene and camera `args` ask for, seeding scene generation with `seed`.
+fn build_camera(args: &Args, seed: Option<u64>) -> Camera {
+    if let Some(seed) = seed {
+        fastrand::seed(seed);
+    }
+    let mut builder = if let Some(path) = &args.scene_file {
+        ray_tracing::scene_file::load(path).unwrap_or_else(|error| exit_with_error(error))
+    } else {
+        match args.scene {
+            Scene::ManySpheres => scenes::many_spheres(),
+            Scene::ManyBouncingSpheres => scenes::many_bouncing_spheres(),
+            Scene::CheckerSpheres => scenes::checkered_spheres(),
+            Scene::Globe => scenes::world(),
+            Scene::Quads => scenes::quads(),
+            Scene::Mesh => scenes::mesh(),
+            Scene::Cube => scenes::cube(),
+            Scene::SimpleLight => scenes::simple_light(),
+            Scene::CornellBox => scenes::cornell_box(),
+            Scene::Instances => scenes::instances(),
+            Scene::CornellSmoke => scenes::cornell_smoke(),
+        }
     };
     if args.draft {
         builder = builder.draft();
     }
-    let camera = builder.build();
-    camera.render_to_file();
+    if let Some(seed) = seed {
+        builder = builder.seed(seed);
+    }
+    builder = builder
+        .checkpoint(args.checkpoint.clone())
+        .checkpoint_interval(Duration::from_secs(args.checkpoint_interval));
+    if args.russian_roulette.is_some() {
+        builder = builder.russian_roulette(args.russian_roulette);
+    }
+    builder = match args.integrator {
+        Integrator::Path => builder.integrator(PathTracer),
+        Integrator::Normals => builder.integrator(Normals),
+        Integrator::Depth => builder.integrator(Depth::default()),
+        Integrator::Uv => builder.integrator(Uv),
+        Integrator::Albedo => builder.integrator(Albedo),
+        Integrator::Heatmap => builder.integrator(Heatmap::default()),
+    };
+    let adaptive = args.adaptive.map(|threshold| Adaptive {
+        min_samples: args.min_samples,
+        max_samples: args.max_samples,
+        threshold,
+    });
+    if let Some(Err(error)) = adaptive.map(|adaptive| adaptive.validate()) {
+        exit_with_error(format!("invalid adaptive sampling: {error}"));
+    }
+    builder = builder.adaptive(adaptive);
+    builder = match args.sampler {
+        Sampler::Independent => builder.sampler(Independent),
+        Sampler::Stratified => builder.sampler(Stratified),
+        Sampler::Halton => builder.sampler(Halton),
+        Sampler::Sobol => builder.sampler(Sobol),
+    };
+    let filter = match args.filter {
+        Filter::Box => camera::Filter::Box {
+            radius: args.filter_radius.unwrap_or(0.5),
+        },
+        Filter::Tent => camera::Filter::Tent {
+            radius: args.filter_radius.unwrap_or(1.0),
+        },
+        Filter::Gaussian => {
+            let radius = args.filter_radius.unwrap_or(1.5);
+            camera::Filter::Gaussian {
+                radius,
+                sigma: radius / 3.0,
+            }
+        }
+        Filter::Mitchell => camera::Filter::Mitchell {
+            radius: args.filter_radius.unwrap_or(2.0),
+            b: 1.0 / 3.0,
+            c: 1.0 / 3.0,
+        },
+        Filter::Lanczos => camera::Filter::Lanczos {
+            radius: args.filter_radius.unwrap_or(2.0),
+        },
+    };
+    if let Err(error) = filter.validate() {
+        exit_with_error(format!("invalid filter: {error}"));
+    }
+    builder = builder.filter(filter);
+    let operator = match args.tone_map {
+        Operator::Clamp => tonemap::Operator::Clamp,
+        Operator::Reinhard => tonemap::Operator::Reinhard,
+        Operator::ExtendedReinhard => tonemap::Operator::ExtendedReinhard {
+            white: args.white_point,
+        },
+        Operator::Aces => tonemap::Operator::Aces,
+        Operator::Agx => tonemap::Operator::Agx,
+    };
+    let tone_map = ToneMap::new(args.exposure, operator)
+        .unwrap_or_else(|error| exit_with_error(format!("invalid tone mapping: {error}")));
+    builder = builder.tone_map(tone_map);
+    builder.build()
+}
+
+fn main() {
+    let args = Args::parse();
+    let format = args
+        .format
+        .or_else(|| Format::from_path(&args.output))
+        .unwrap_or_else(|| {
+            exit_with_error(format!(
+                "can't tell the image format of {}, pass --format",
+                args.output.display()
+            ))
+        });
+    let heatmap_format = args.sample_heatmap.as_ref().map(|path| {
+        Format::from_path(path).unwrap_or_else(|| {
+            exit_with_error(format!("can't tell the image format of {}", path.display()))
+        })
+    });
+
+    let checkpoint = args
+        .checkpoint
+        .as_ref()
+        .filter(|_| args.resume)
+        .map(|path| Checkpoint::load(path).unwrap_or_else(|error| exit_with_error(error)));
+    let seed = choose_seed(&args, checkpoint.as_ref().map(Checkpoint::seed));
+    let camera = build_camera(&args, seed);
+    let save = |film: &Film| {
+        camera
+            .save(film, &args.output, format)
+            .unwrap_or_else(|error| {
+                exit_with_error(format!("failed to save {}: {error}", args.output.display()))
+            });
+    };
+    let mut saved = false;
+    let on_pass = |film: &Film, samples| {
+        if args.progressive {
+            save(film);
+            saved = true;
+            println!(
+                "Saved {} at {samples} samples per pixel",
+                args.output.display()
+            );
+        }
+        ControlFlow::Continue(())
+    };
+    let film = match checkpoint {
+        Some(checkpoint) => camera
+            .resume(checkpoint, args.progressive, on_pass)
+            .unwrap_or_else(|error| exit_with_error(error)),
+        None if args.progressive => camera.render_progressive(on_pass),
+        None => camera.render_film(),
+    };
+    // A resumed render may have had no passes left to do
+    if !saved {
+        save(&film);
+    }
+    if let (Some(path), Some(format)) = (&args.sample_heatmap, heatmap_format) {
+        let heatmap = film.sample_heatmap();
+        output::save(&heatmap, film.width(), film.height(), path, format).unwrap_or_else(|error| {
+            exit_with_error(format!("failed to save {}: {error}", path.display()))
+        });
+    }
+    if args.archive {
+        output::archive(&args.output, Path::new("out")).unwrap_or_else(|error| {
+            exit_with_error(format!(
+                "failed to archive {}: {error}",
+                args.output.display()
+            ))
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resuming_without_a_seed_builds_the_same_scene() {
+        let args = |extra: &[&str]| {
+            let base = ["ray-tracing", "--draft", "--checkpoint", "render.ckpt"];
+            Args::parse_from(base.iter().chain(extra))
+        };
+        let started = args(&[]);
+        let seed = choose_seed(&started, None);
+        assert!(seed.is_some());
+        let original = build_camera(&started, seed);
+
+        // Resuming takes the seed from the checkpoint, which the render saved it in
+        let resumed = args(&["--resume"]);
+        let camera = build_camera(&resumed, choose_seed(&resumed, seed));
+        assert_eq!(camera.scene_hash(), original.scene_hash());
+    }
 }
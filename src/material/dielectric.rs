@@ -1,3 +1,7 @@
+use std::hash::Hasher;
+
+use glam::Vec2;
+
 use crate::{
     camera::Stores,
     color::{
@@ -5,6 +9,7 @@ use crate::{
         WHITE,
     },
     extension_traits::Vec3Ext,
+    fingerprint::Fingerprint,
     hittable::HitRecord,
     material::Material,
     timed_ray::TimedRay,
@@ -28,8 +33,20 @@ impl Dielectric {
     }
 }
 
+impl Fingerprint for Dielectric {
+    fn fingerprint(&self, hasher: &mut dyn Hasher) {
+        "Dielectric".fingerprint(hasher);
+        self.refraction_index.fingerprint(hasher);
+    }
+}
+
 impl Material for Dielectric {
-    fn scatter(&self, hit_record: &HitRecord, _stores: &Stores) -> Option<(TimedRay, Color)> {
+    fn scatter(
+        &self,
+        hit_record: &HitRecord,
+        u: Vec2,
+        _stores: &Stores,
+    ) -> Option<(TimedRay, Color)> {
         let refraction_index = if hit_record.front_face {
             1.0 / self.refraction_index
         } else {
@@ -41,12 +58,11 @@ impl Material for Dielectric {
         let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
 
         let cannot_refract = refraction_index * sin_theta > 1.0;
-        let direction =
-            if cannot_refract || Self::reflectance(cos_theta, refraction_index) > fastrand::f32() {
-                unit_direction.reflect(hit_record.normal)
-            } else {
-                unit_direction.refract_custom(hit_record.normal, refraction_index)
-            };
+        let direction = if cannot_refract || Self::reflectance(cos_theta, refraction_index) > u.x {
+            unit_direction.reflect(hit_record.normal)
+        } else {
+            unit_direction.refract_custom(hit_record.normal, refraction_index)
+        };
 
         let scattered = TimedRay::new(hit_record.point, direction, hit_record.in_ray.time);
         Some((scattered, WHITE))
@@ -0,0 +1,49 @@
+use std::hash::Hasher;
+
+use glam::Vec2;
+
+use super::Material;
+use crate::{
+    camera::Stores,
+    color::Color,
+    fingerprint::Fingerprint,
+    hittable::HitRecord,
+    texture::TextureHandle,
+    timed_ray::TimedRay,
+};
+
+#[derive(Debug)]
+pub struct DiffuseLight {
+    texture: TextureHandle,
+}
+
+impl DiffuseLight {
+    pub fn new(texture: TextureHandle) -> Self {
+        Self { texture }
+    }
+}
+
+impl Fingerprint for DiffuseLight {
+    fn fingerprint(&self, hasher: &mut dyn Hasher) {
+        "DiffuseLight".fingerprint(hasher);
+        self.texture.fingerprint(hasher);
+    }
+}
+
+impl Material for DiffuseLight {
+    fn scatter(
+        &self,
+        _hit_record: &HitRecord,
+        _u: Vec2,
+        _stores: &Stores,
+    ) -> Option<(TimedRay, Color)> {
+        None
+    }
+
+    fn emitted(&self, hit_record: &HitRecord, stores: &Stores) -> Color {
+        stores
+            .textures
+            .get(self.texture)
+            .value(hit_record.uv, hit_record.point)
+    }
+}
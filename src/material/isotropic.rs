@@ -0,0 +1,71 @@
+use std::{
+    f32::consts::PI,
+    hash::Hasher,
+};
+
+use glam::{
+    Vec2,
+    Vec3A as Vec3,
+};
+
+use super::Material;
+use crate::{
+    camera::Stores,
+    color::Color,
+    extension_traits::Vec3Ext,
+    fingerprint::Fingerprint,
+    hittable::HitRecord,
+    texture::TextureHandle,
+    timed_ray::TimedRay,
+};
+
+/// A phase function that scatters equally in every direction, for use inside volumes.
+#[derive(Debug)]
+pub struct Isotropic {
+    texture: TextureHandle,
+}
+
+impl Isotropic {
+    pub fn new(texture: TextureHandle) -> Self {
+        Self { texture }
+    }
+}
+
+impl Fingerprint for Isotropic {
+    fn fingerprint(&self, hasher: &mut dyn Hasher) {
+        "Isotropic".fingerprint(hasher);
+        self.texture.fingerprint(hasher);
+    }
+}
+
+impl Material for Isotropic {
+    fn scatter(
+        &self,
+        hit_record: &HitRecord,
+        u: Vec2,
+        stores: &Stores,
+    ) -> Option<(TimedRay, Color)> {
+        let scattered = TimedRay::new(
+            hit_record.point,
+            Vec3::sample_unit_sphere(u),
+            hit_record.in_ray.time,
+        );
+        let attenuation = stores
+            .textures
+            .get(self.texture)
+            .value(hit_record.uv, hit_record.point);
+        Some((scattered, attenuation))
+    }
+
+    fn eval(&self, hit_record: &HitRecord, _direction: Vec3, stores: &Stores) -> Option<Color> {
+        let albedo = stores
+            .textures
+            .get(self.texture)
+            .value(hit_record.uv, hit_record.point);
+        Some(albedo * (1.0 / (4.0 * PI)))
+    }
+
+    fn scatter_pdf(&self, _hit_record: &HitRecord, _direction: Vec3, _stores: &Stores) -> f32 {
+        1.0 / (4.0 * PI)
+    }
+}
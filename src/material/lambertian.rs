@@ -1,10 +1,19 @@
-use glam::Vec3A as Vec3;
+use std::{
+    f32::consts::PI,
+    hash::Hasher,
+};
+
+use glam::{
+    Vec2,
+    Vec3A as Vec3,
+};
 
 use super::Material;
 use crate::{
     camera::Stores,
     color::Color,
     extension_traits::Vec3Ext,
+    fingerprint::Fingerprint,
     hittable::HitRecord,
     texture::TextureHandle,
     timed_ray::TimedRay,
@@ -21,9 +30,21 @@ impl Lambertian {
     }
 }
 
+impl Fingerprint for Lambertian {
+    fn fingerprint(&self, hasher: &mut dyn Hasher) {
+        "Lambertian".fingerprint(hasher);
+        self.texture.fingerprint(hasher);
+    }
+}
+
 impl Material for Lambertian {
-    fn scatter(&self, hit_record: &HitRecord, stores: &Stores) -> Option<(TimedRay, Color)> {
-        let mut scatter_direction = hit_record.normal + Vec3::random_unit_vector();
+    fn scatter(
+        &self,
+        hit_record: &HitRecord,
+        u: Vec2,
+        stores: &Stores,
+    ) -> Option<(TimedRay, Color)> {
+        let mut scatter_direction = hit_record.normal + Vec3::sample_unit_sphere(u);
         if scatter_direction.near_zero() {
             scatter_direction = hit_record.normal;
         }
@@ -34,4 +55,17 @@ impl Material for Lambertian {
             .value(hit_record.uv, hit_record.point);
         Some((scattered, attenuation))
     }
+
+    fn eval(&self, hit_record: &HitRecord, direction: Vec3, stores: &Stores) -> Option<Color> {
+        let cosine = hit_record.normal.dot(direction.normalize()).max(0.0);
+        let albedo = stores
+            .textures
+            .get(self.texture)
+            .value(hit_record.uv, hit_record.point);
+        Some(albedo * (cosine / PI))
+    }
+
+    fn scatter_pdf(&self, hit_record: &HitRecord, direction: Vec3, _stores: &Stores) -> f32 {
+        hit_record.normal.dot(direction.normalize()).max(0.0) / PI
+    }
 }
@@ -1,10 +1,19 @@
-use glam::Vec3A as Vec3;
+use std::{
+    f32::consts::PI,
+    hash::Hasher,
+};
+
+use glam::{
+    Vec2,
+    Vec3A as Vec3,
+};
 
 use super::Material;
 use crate::{
     camera::Stores,
     color::Color,
     extension_traits::Vec3Ext,
+    fingerprint::Fingerprint,
     hittable::HitRecord,
     timed_ray::TimedRay,
 };
@@ -22,11 +31,89 @@ impl Metal {
     }
 }
 
+impl Fingerprint for Metal {
+    fn fingerprint(&self, hasher: &mut dyn Hasher) {
+        "Metal".fingerprint(hasher);
+        self.albedo.fingerprint(hasher);
+        self.fuzz.fingerprint(hasher);
+    }
+}
+
 impl Material for Metal {
-    fn scatter(&self, hit_record: &HitRecord, _stores: &Stores) -> Option<(TimedRay, Color)> {
+    fn scatter(
+        &self,
+        hit_record: &HitRecord,
+        u: Vec2,
+        _stores: &Stores,
+    ) -> Option<(TimedRay, Color)> {
         let mut reflected = hit_record.in_ray.direction.reflect(hit_record.normal);
-        reflected = reflected.normalize() + Vec3::random_unit_vector() * self.fuzz;
+        reflected = reflected.normalize() + Vec3::sample_unit_sphere(u) * self.fuzz;
         let scattered = TimedRay::new(hit_record.point, reflected, hit_record.in_ray.time);
         (scattered.direction.dot(hit_record.normal) > 0.0).then_some((scattered, self.albedo))
     }
+
+    fn eval(&self, hit_record: &HitRecord, direction: Vec3, stores: &Stores) -> Option<Color> {
+        // A perfect mirror only reflects one direction, which sampled lights never hit
+        if self.fuzz == 0.0 {
+            return None;
+        }
+        // Directions below the surface are absorbed, and the rest keep the albedo
+        Some(self.albedo * self.scatter_pdf(hit_record, direction, stores))
+    }
+
+    /// `scatter` offsets the mirror direction by a point on a sphere of radius `fuzz`,
+    /// so this projects the uniform density on that sphere onto the directions through
+    /// it.
+    fn scatter_pdf(&self, hit_record: &HitRecord, direction: Vec3, _stores: &Stores) -> f32 {
+        let direction = direction.normalize();
+        if self.fuzz == 0.0 || direction.dot(hit_record.normal) <= 0.0 {
+            return 0.0;
+        }
+
+        let reflected = hit_record
+            .in_ray
+            .direction
+            .normalize()
+            .reflect(hit_record.normal);
+        let b = direction.dot(reflected);
+        let discriminant = b * b - 1.0 + self.fuzz * self.fuzz;
+        if discriminant <= 0.0 {
+            return 0.0;
+        }
+
+        // Both points where the direction crosses the sphere contribute
+        let sqrt_d = discriminant.sqrt();
+        let near = (b - sqrt_d).max(0.0);
+        let far = b + sqrt_d;
+        if far <= 0.0 {
+            return 0.0;
+        }
+        (near * near + far * far) / (4.0 * PI * self.fuzz * sqrt_d)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::assert_pdf_integrates_to_one;
+
+    #[test]
+    fn test_scatter_pdf_integrates_to_one() {
+        let metal = Metal::new(Color::new(1.0, 1.0, 1.0), 0.5);
+        let hit_record = HitRecord {
+            point: Vec3::ZERO,
+            uv: Vec2::ZERO,
+            normal: Vec3::Y,
+            t: 1.0,
+            front_face: true,
+            in_ray: TimedRay::new(Vec3::new(-1.0, 1.0, 0.0), Vec3::new(1.0, -1.0, 0.0), 0.0),
+            material: &metal,
+        };
+        let stores = Stores::default();
+
+        // All scattered directions are above this surface, so none are absorbed
+        assert_pdf_integrates_to_one(|direction| {
+            metal.scatter_pdf(&hit_record, direction, &stores)
+        });
+    }
 }
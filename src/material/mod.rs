@@ -1,4 +1,6 @@
 mod dielectric;
+mod diffuse_light;
+mod isotropic;
 mod lambertian;
 mod metal;
 mod uniform;
@@ -6,18 +8,75 @@ mod uniform;
 use std::fmt::Debug;
 
 pub use dielectric::Dielectric;
+pub use diffuse_light::DiffuseLight;
+use glam::{
+    Vec2,
+    Vec3A as Vec3,
+};
+pub use isotropic::Isotropic;
 pub use lambertian::Lambertian;
 pub use metal::Metal;
 pub use uniform::Uniform;
 
 use crate::{
     camera::Stores,
-    color::Color,
+    color::{
+        Color,
+        BLACK,
+    },
+    fingerprint::Fingerprint,
     hittable::HitRecord,
     timed_ray::TimedRay,
 };
 
-pub trait Material: Sync + Debug {
+pub trait Material: Send + Sync + Debug + Fingerprint {
+    /// Picks the direction to continue in, based on `u`, a pair of numbers in `[0, 1)`
+    /// that the camera's sampler spreads out over the samples.
     // TODO: Passing in stores is pretty bad, but it works for now
-    fn scatter(&self, hit_record: &HitRecord, stores: &Stores) -> Option<(TimedRay, Color)>;
+    fn scatter(
+        &self,
+        hit_record: &HitRecord,
+        u: Vec2,
+        stores: &Stores,
+    ) -> Option<(TimedRay, Color)>;
+
+    fn emitted(&self, _hit_record: &HitRecord, _stores: &Stores) -> Color {
+        BLACK
+    }
+
+    /// The BSDF times the cosine term for light arriving from `direction`, used to
+    /// weight sampled lights. `None` means the material only scatters in particular
+    /// directions, like a mirror or glass, so sampled lights can't reach it.
+    fn eval(&self, _hit_record: &HitRecord, _direction: Vec3, _stores: &Stores) -> Option<Color> {
+        None
+    }
+
+    /// The pdf with respect to solid angle of `scatter` sending the ray along
+    /// `direction`, for materials that `eval` supports.
+    fn scatter_pdf(&self, _hit_record: &HitRecord, _direction: Vec3, _stores: &Stores) -> f32 {
+        0.0
+    }
+}
+
+impl Material for Box<dyn Material> {
+    fn scatter(
+        &self,
+        hit_record: &HitRecord,
+        u: Vec2,
+        stores: &Stores,
+    ) -> Option<(TimedRay, Color)> {
+        self.as_ref().scatter(hit_record, u, stores)
+    }
+
+    fn emitted(&self, hit_record: &HitRecord, stores: &Stores) -> Color {
+        self.as_ref().emitted(hit_record, stores)
+    }
+
+    fn eval(&self, hit_record: &HitRecord, direction: Vec3, stores: &Stores) -> Option<Color> {
+        self.as_ref().eval(hit_record, direction, stores)
+    }
+
+    fn scatter_pdf(&self, hit_record: &HitRecord, direction: Vec3, stores: &Stores) -> f32 {
+        self.as_ref().scatter_pdf(hit_record, direction, stores)
+    }
 }
@@ -1,8 +1,19 @@
+use std::{
+    f32::consts::PI,
+    hash::Hasher,
+};
+
+use glam::{
+    Vec2,
+    Vec3A as Vec3,
+};
+
 use super::Material;
 use crate::{
     camera::Stores,
     color::Color,
     extension_traits::Vec3Ext,
+    fingerprint::Fingerprint,
     hittable::HitRecord,
     timed_ray::TimedRay,
 };
@@ -18,10 +29,39 @@ impl Uniform {
     }
 }
 
+impl Fingerprint for Uniform {
+    fn fingerprint(&self, hasher: &mut dyn Hasher) {
+        "Uniform".fingerprint(hasher);
+        self.albedo.fingerprint(hasher);
+    }
+}
+
 impl Material for Uniform {
-    fn scatter(&self, hit_record: &HitRecord, _stores: &Stores) -> Option<(TimedRay, Color)> {
-        let scatter_direction = hit_record.normal.random_in_hemisphere();
+    fn scatter(
+        &self,
+        hit_record: &HitRecord,
+        u: Vec2,
+        _stores: &Stores,
+    ) -> Option<(TimedRay, Color)> {
+        let mut scatter_direction = Vec3::sample_unit_sphere(u);
+        if scatter_direction.dot(hit_record.normal) < 0.0 {
+            scatter_direction = -scatter_direction;
+        }
         let scattered = TimedRay::new(hit_record.point, scatter_direction, hit_record.in_ray.time);
         Some((scattered, self.albedo))
     }
+
+    fn eval(&self, hit_record: &HitRecord, direction: Vec3, stores: &Stores) -> Option<Color> {
+        // Scattering is uniform over the hemisphere rather than cosine weighted, so the
+        // attenuation is the same for every direction
+        Some(self.albedo * self.scatter_pdf(hit_record, direction, stores))
+    }
+
+    fn scatter_pdf(&self, hit_record: &HitRecord, direction: Vec3, _stores: &Stores) -> f32 {
+        if hit_record.normal.dot(direction) > 0.0 {
+            1.0 / (2.0 * PI)
+        } else {
+            0.0
+        }
+    }
 }
@@ -0,0 +1,391 @@
+mod mtl;
+
+use std::{
+    collections::HashMap,
+    error::Error,
+    fmt,
+    fs,
+    io,
+    path::{
+        Path,
+        PathBuf,
+    },
+};
+
+use glam::{
+    Vec2,
+    Vec3A as Vec3,
+};
+use mtl::MtlMaterial;
+
+use crate::{
+    camera::Stores,
+    hittable::TriangleMesh,
+    material::Material,
+};
+
+#[derive(Debug)]
+pub enum ObjError {
+    Io {
+        path: PathBuf,
+        source: io::Error,
+    },
+    Parse {
+        path: PathBuf,
+        line: usize,
+        message: String,
+    },
+    Texture {
+        path: PathBuf,
+        source: image::ImageError,
+    },
+}
+
+impl fmt::Display for ObjError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io { path, source } => write!(f, "{}: {source}", path.display()),
+            Self::Parse {
+                path,
+                line,
+                message,
+            } => write!(f, "{}:{line}: {message}", path.display()),
+            Self::Texture { path, source } => {
+                write!(f, "{}: failed to load texture: {source}", path.display())
+            }
+        }
+    }
+}
+
+impl Error for ObjError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io { source, .. } => Some(source),
+            Self::Parse { .. } => None,
+            Self::Texture { source, .. } => Some(source),
+        }
+    }
+}
+
+/// Indices into the position, texture coordinate and normal lists of an OBJ file.
+type VertexRef = (usize, Option<usize>, Option<usize>);
+
+struct Group {
+    material: Option<(String, usize)>,
+    faces: Vec<[VertexRef; 3]>,
+}
+
+#[derive(Default)]
+struct Obj {
+    positions: Vec<Vec3>,
+    uvs: Vec<Vec2>,
+    normals: Vec<Vec3>,
+    material_libs: Vec<String>,
+    groups: Vec<Group>,
+}
+
+/// Loads a Wavefront OBJ file and any MTL libraries it references.
+///
+/// Faces are grouped by material into one `TriangleMesh` each. Diffuse maps are
+/// added to `stores.textures`, and meshes with emissive materials to `stores.lights`
+/// as well, so they should be placed in the world as they are.
+///
+/// # Errors
+///
+/// Returns an error if a file can't be read, contains malformed statements or
+/// references a material or texture that doesn't exist.
+pub fn load(path: impl AsRef<Path>, stores: &mut Stores) -> Result<Vec<TriangleMesh>, ObjError> {
+    let path = path.as_ref();
+    let obj = Obj::parse(&read(path)?, path)?;
+
+    let dir = path.parent().unwrap_or(Path::new(""));
+    let mut materials = HashMap::new();
+    let mut texture_cache = HashMap::new();
+    for lib in &obj.material_libs {
+        let lib_path = dir.join(lib);
+        let source = read(&lib_path)?;
+        materials.extend(mtl::parse(
+            &source,
+            &lib_path,
+            &mut stores.textures,
+            &mut texture_cache,
+        )?);
+    }
+
+    obj.groups
+        .iter()
+        .filter(|group| !group.faces.is_empty())
+        .map(|group| {
+            let material = match &group.material {
+                Some((name, line)) => materials.get(name).ok_or_else(|| ObjError::Parse {
+                    path: path.to_path_buf(),
+                    line: *line,
+                    message: format!("unknown material '{name}'"),
+                })?,
+                None => &MtlMaterial::default(),
+            };
+            let mesh = obj.mesh(group, material.build(&mut stores.textures));
+            if material.emits() {
+                stores.lights.add(mesh.clone());
+            }
+            Ok(mesh)
+        })
+        .collect()
+}
+
+fn read(path: &Path) -> Result<String, ObjError> {
+    fs::read_to_string(path).map_err(|source| ObjError::Io {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+impl Obj {
+    fn parse(source: &str, path: &Path) -> Result<Self, ObjError> {
+        let mut obj = Self::default();
+
+        for (index, line) in source.lines().enumerate() {
+            let line_number = index + 1;
+            let error = |message: String| ObjError::Parse {
+                path: path.to_path_buf(),
+                line: line_number,
+                message,
+            };
+
+            let line = line.split('#').next().unwrap().trim();
+            let mut tokens = line.split_whitespace();
+            let Some(keyword) = tokens.next() else {
+                continue;
+            };
+            let args: Vec<_> = tokens.collect();
+
+            match keyword {
+                "v" => match parse_floats(&args).map_err(error)?[..] {
+                    [x, y, z] | [x, y, z, _] => obj.positions.push(Vec3::new(x, y, z)),
+                    _ => return Err(error("v expects 3 or 4 numbers".to_string())),
+                },
+                "vt" => match parse_floats(&args).map_err(error)?[..] {
+                    [u] => obj.uvs.push(Vec2::new(u, 0.0)),
+                    [u, v] | [u, v, _] => obj.uvs.push(Vec2::new(u, v)),
+                    _ => return Err(error("vt expects 1 to 3 numbers".to_string())),
+                },
+                "vn" => match parse_floats(&args).map_err(error)?[..] {
+                    [x, y, z] => obj.normals.push(Vec3::new(x, y, z)),
+                    _ => return Err(error("vn expects 3 numbers".to_string())),
+                },
+                "f" => {
+                    if args.len() < 3 {
+                        return Err(error(format!(
+                            "face needs at least 3 vertices, found {}",
+                            args.len()
+                        )));
+                    }
+                    let vertices = args
+                        .iter()
+                        .map(|arg| obj.parse_vertex(arg))
+                        .collect::<Result<Vec<_>, _>>()
+                        .map_err(error)?;
+                    if obj.groups.is_empty() {
+                        obj.groups.push(Group {
+                            material: None,
+                            faces: Vec::new(),
+                        });
+                    }
+                    let faces = &mut obj.groups.last_mut().unwrap().faces;
+                    // Polygons are assumed convex and split into a fan
+                    for i in 1..vertices.len() - 1 {
+                        faces.push([vertices[0], vertices[i], vertices[i + 1]]);
+                    }
+                }
+                "usemtl" => {
+                    let name = args.join(" ");
+                    if name.is_empty() {
+                        return Err(error("usemtl is missing a name".to_string()));
+                    }
+                    obj.groups.push(Group {
+                        material: Some((name, line_number)),
+                        faces: Vec::new(),
+                    });
+                }
+                "mtllib" => {
+                    if args.is_empty() {
+                        return Err(error("mtllib is missing a file name".to_string()));
+                    }
+                    obj.material_libs
+                        .extend(args.iter().map(|&lib| lib.to_string()));
+                }
+                // Object and group names, smoothing groups, lines and points don't affect rendering
+                _ => {}
+            }
+        }
+
+        Ok(obj)
+    }
+
+    fn parse_vertex(&self, arg: &str) -> Result<VertexRef, String> {
+        let mut parts = arg.split('/');
+        let position = parts.next().unwrap();
+        let uv = parts.next().filter(|s| !s.is_empty());
+        let normal = parts.next().filter(|s| !s.is_empty());
+        if parts.next().is_some() {
+            return Err(format!("invalid face vertex '{arg}'"));
+        }
+
+        Ok((
+            resolve_index(position, self.positions.len(), "position")?,
+            uv.map(|uv| resolve_index(uv, self.uvs.len(), "texture coordinate"))
+                .transpose()?,
+            normal
+                .map(|normal| resolve_index(normal, self.normals.len(), "normal"))
+                .transpose()?,
+        ))
+    }
+
+    fn mesh(&self, group: &Group, material: Box<dyn Material>) -> TriangleMesh {
+        // OBJ indexes each attribute separately, so every distinct combination becomes
+        // one mesh vertex
+        let mut vertex_indices = HashMap::new();
+        let mut vertices = Vec::new();
+        let indices = group
+            .faces
+            .iter()
+            .map(|face| {
+                face.map(|vertex| {
+                    *vertex_indices.entry(vertex).or_insert_with(|| {
+                        vertices.push(vertex);
+                        (vertices.len() - 1) as u32
+                    })
+                })
+            })
+            .collect();
+
+        let positions = vertices.iter().map(|v| self.positions[v.0]).collect();
+        let uvs = vertices
+            .iter()
+            .map(|v| v.1.map(|i| self.uvs[i]))
+            .collect::<Option<_>>();
+        let normals = vertices
+            .iter()
+            .map(|v| v.2.map(|i| self.normals[i]))
+            .collect::<Option<_>>();
+
+        TriangleMesh::new(positions, indices, normals, uvs, material)
+    }
+}
+
+fn resolve_index(index: &str, len: usize, kind: &str) -> Result<usize, String> {
+    let index: isize = index
+        .parse()
+        .map_err(|_| format!("invalid {kind} index '{index}'"))?;
+    // Positive indices are 1-based, negative ones count back from the latest element
+    let resolved = match index {
+        1.. => Some(index.unsigned_abs() - 1),
+        ..0 => len.checked_sub(index.unsigned_abs()),
+        0 => return Err(format!("{kind} index must not be 0")),
+    };
+    resolved
+        .filter(|&resolved| resolved < len)
+        .ok_or_else(|| format!("{kind} index {index} is out of range, only {len} defined so far"))
+}
+
+fn parse_floats(args: &[&str]) -> Result<Vec<f32>, String> {
+    args.iter()
+        .map(|arg| arg.parse().map_err(|_| format!("invalid number '{arg}'")))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(source: &str) -> Result<Obj, ObjError> {
+        Obj::parse(source, Path::new("test.obj"))
+    }
+
+    #[test]
+    fn test_quad_is_triangulated() {
+        let obj = parse(
+            "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nvt 0 0\nvn 0 0 1\nf 1/1/1 2/1/1 3/1/1 \
+             -1/1/1\n",
+        )
+        .unwrap();
+        let faces = &obj.groups[0].faces;
+        assert_eq!(faces.len(), 2);
+        assert_eq!(
+            faces[1],
+            [
+                (0, Some(0), Some(0)),
+                (2, Some(0), Some(0)),
+                (3, Some(0), Some(0))
+            ]
+        );
+    }
+
+    #[test]
+    fn test_usemtl_starts_group() {
+        let obj = parse("v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\nusemtl red\nf 3 2 1\n").unwrap();
+        assert_eq!(obj.groups.len(), 2);
+        assert_eq!(obj.groups[1].material, Some(("red".to_string(), 5)));
+    }
+
+    #[test]
+    fn test_errors_report_line() {
+        let error = parse("v 0 0 0\nv 1 0\n").err().unwrap();
+        assert_eq!(error.to_string(), "test.obj:2: v expects 3 or 4 numbers");
+
+        let error = parse("v 0 0 0\nf 1 2 3\n").err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "test.obj:2: position index 2 is out of range, only 1 defined so far"
+        );
+    }
+
+    #[test]
+    fn test_missing_material_library_is_io_error() {
+        let name = format!("ray-tracing-obj-test-{}", std::process::id());
+        let dir = std::env::temp_dir().join(name);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("mesh.obj");
+        fs::write(
+            &path,
+            "mtllib missing.mtl
+v 0 0 0
+v 1 0 0
+v 0 1 0
+f 1 2 3
+",
+        )
+        .unwrap();
+
+        let error = load(&path, &mut Stores::default()).err().unwrap();
+        assert!(matches!(&error, ObjError::Io { path, .. } if path == &dir.join("missing.mtl")));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_emissive_meshes_are_lights() {
+        let name = format!("ray-tracing-obj-light-test-{}", std::process::id());
+        let dir = std::env::temp_dir().join(name);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("lamp.mtl"), "newmtl lamp\nKe 4 4 4\n").unwrap();
+        let path = dir.join("lamp.obj");
+        fs::write(
+            &path,
+            "mtllib lamp.mtl
+v 0 0 0
+v 1 0 0
+v 0 1 0
+f 1 2 3
+usemtl lamp
+f 1 3 2
+",
+        )
+        .unwrap();
+
+        let mut stores = Stores::default();
+        let meshes = load(&path, &mut stores).unwrap();
+        assert_eq!(meshes.len(), 2);
+        assert_eq!(stores.lights.objects.len(), 1);
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,207 @@
+use std::{
+    collections::HashMap,
+    path::{
+        Path,
+        PathBuf,
+    },
+};
+
+use super::{
+    parse_floats,
+    ObjError,
+};
+use crate::{
+    color::Color,
+    material::{
+        Dielectric,
+        DiffuseLight,
+        Lambertian,
+        Material,
+        Metal,
+    },
+    texture::{
+        ImageTexture,
+        SolidColor,
+        TextureHandle,
+        TextureStore,
+    },
+};
+
+/// The subset of an MTL material that maps onto our own materials.
+#[derive(Debug, Clone)]
+pub(super) struct MtlMaterial {
+    diffuse: Color,
+    specular: Option<Color>,
+    emission: Color,
+    shininess: f32,
+    refraction_index: f32,
+    dissolve: f32,
+    illum: u32,
+    diffuse_map: Option<TextureHandle>,
+}
+
+impl Default for MtlMaterial {
+    fn default() -> Self {
+        Self {
+            diffuse: Color::new(0.8, 0.8, 0.8),
+            specular: None,
+            emission: Color::default(),
+            shininess: 0.0,
+            refraction_index: 1.5,
+            dissolve: 1.0,
+            illum: 1,
+            diffuse_map: None,
+        }
+    }
+}
+
+impl MtlMaterial {
+    pub(super) fn emits(&self) -> bool {
+        self.emission.0.max_element() > 0.0
+    }
+
+    pub(super) fn build(&self, textures: &mut TextureStore) -> Box<dyn Material> {
+        if self.emits() {
+            let texture = textures.add(SolidColor::new_from_color(self.emission));
+            return Box::new(DiffuseLight::new(texture));
+        }
+
+        match self.illum {
+            4 | 6 | 7 | 9 => Box::new(Dielectric::new(self.refraction_index)),
+            _ if self.dissolve < 1.0 => Box::new(Dielectric::new(self.refraction_index)),
+            3 | 5 | 8 => {
+                // Map the Phong exponent onto a roughness so that shinier means less fuzz
+                let fuzz = (2.0 / (self.shininess + 2.0)).sqrt().clamp(0.0, 1.0);
+                // Exporters often leave out Ks and put the metal's color in Kd
+                Box::new(Metal::new(self.specular.unwrap_or(self.diffuse), fuzz))
+            }
+            _ => {
+                let texture = self
+                    .diffuse_map
+                    .unwrap_or_else(|| textures.add(SolidColor::new_from_color(self.diffuse)));
+                Box::new(Lambertian::new(texture))
+            }
+        }
+    }
+}
+
+pub(super) fn parse(
+    source: &str,
+    path: &Path,
+    textures: &mut TextureStore,
+    texture_cache: &mut HashMap<PathBuf, TextureHandle>,
+) -> Result<HashMap<String, MtlMaterial>, ObjError> {
+    let dir = path.parent().unwrap_or(Path::new(""));
+    let mut materials = HashMap::new();
+    let mut current: Option<(String, MtlMaterial)> = None;
+
+    for (index, line) in source.lines().enumerate() {
+        let line_number = index + 1;
+        let error = |message: String| ObjError::Parse {
+            path: path.to_path_buf(),
+            line: line_number,
+            message,
+        };
+
+        let line = line.split('#').next().unwrap().trim();
+        let mut tokens = line.split_whitespace();
+        let Some(keyword) = tokens.next() else {
+            continue;
+        };
+        let args: Vec<_> = tokens.collect();
+
+        if keyword == "newmtl" {
+            let name = args.join(" ");
+            if name.is_empty() {
+                return Err(error("newmtl is missing a name".to_string()));
+            }
+            if let Some((name, material)) = current.replace((name, MtlMaterial::default())) {
+                materials.insert(name, material);
+            }
+            continue;
+        }
+
+        let Some((_, material)) = current.as_mut() else {
+            return Err(error(format!("'{keyword}' appears before any newmtl")));
+        };
+
+        match keyword {
+            "Kd" => material.diffuse = parse_color(&args).map_err(error)?,
+            "Ks" => material.specular = Some(parse_color(&args).map_err(error)?),
+            "Ke" => material.emission = parse_color(&args).map_err(error)?,
+            "Ns" => material.shininess = parse_scalar(&args).map_err(error)?,
+            "Ni" => material.refraction_index = parse_scalar(&args).map_err(error)?,
+            "d" => material.dissolve = parse_scalar(&args).map_err(error)?,
+            "Tr" => material.dissolve = 1.0 - parse_scalar(&args).map_err(error)?,
+            "illum" => {
+                material.illum = match args.as_slice() {
+                    [value] => value
+                        .parse()
+                        .map_err(|_| error(format!("invalid illumination model '{value}'")))?,
+                    _ => return Err(error("illum expects one integer".to_string())),
+                };
+            }
+            "map_Kd" => {
+                // Options like `-s 1 1 1` come before the file name, which we take to be last
+                let Some(file) = args.last() else {
+                    return Err(error("map_Kd is missing a file name".to_string()));
+                };
+                let texture_path = dir.join(file);
+                let handle = if let Some(&handle) = texture_cache.get(&texture_path) {
+                    handle
+                } else {
+                    let texture =
+                        ImageTexture::open(&texture_path).map_err(|source| ObjError::Texture {
+                            path: texture_path.clone(),
+                            source,
+                        })?;
+                    let handle = textures.add(texture);
+                    texture_cache.insert(texture_path, handle);
+                    handle
+                };
+                material.diffuse_map = Some(handle);
+            }
+            _ => {}
+        }
+    }
+
+    if let Some((name, material)) = current {
+        materials.insert(name, material);
+    }
+    Ok(materials)
+}
+
+fn parse_color(args: &[&str]) -> Result<Color, String> {
+    match parse_floats(args)?[..] {
+        [r, g, b] => Ok(Color::new(r, g, b)),
+        [v] => Ok(Color::new(v, v, v)),
+        _ => Err(format!("expected 1 or 3 numbers, found {}", args.len())),
+    }
+}
+
+fn parse_scalar(args: &[&str]) -> Result<f32, String> {
+    match parse_floats(args)?[..] {
+        [v] => Ok(v),
+        _ => Err(format!("expected 1 number, found {}", args.len())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_metal_without_specular_takes_diffuse_color() {
+        let source = "newmtl gold\nKd 0.75 0.5 0.25\nillum 3\n";
+        let mut textures = TextureStore::default();
+        let materials = parse(
+            source,
+            Path::new("test.mtl"),
+            &mut textures,
+            &mut HashMap::new(),
+        )
+        .unwrap();
+        let material = materials["gold"].build(&mut textures);
+        assert!(format!("{material:?}").contains("0.75, 0.5, 0.25"));
+    }
+}
@@ -0,0 +1,266 @@
+//! Saving rendered images.
+
+use std::{
+    fmt,
+    fs::{
+        self,
+        File,
+    },
+    io::{
+        self,
+        BufWriter,
+        Write,
+    },
+    path::{
+        Path,
+        PathBuf,
+    },
+    str::FromStr,
+};
+
+use chrono::Local;
+use exr::prelude::f16;
+use image::{
+    error::{
+        EncodingError,
+        ImageFormatHint,
+    },
+    ColorType,
+    ImageError,
+    ImageFormat,
+    ImageResult,
+    Rgb,
+    Rgb32FImage,
+};
+
+use crate::color::Color;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Png,
+    Jpeg,
+    Bmp,
+    Tiff,
+    /// EXR with 32-bit float channels.
+    Exr,
+    /// EXR with 16-bit float channels, which halves the file size.
+    ExrHalf,
+    /// Radiance RGBE.
+    Hdr,
+    /// Portable float map.
+    Pfm,
+}
+
+impl Format {
+    /// Also accepts `exr-half`, which is never picked from a file name.
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        match extension.to_ascii_lowercase().as_str() {
+            "png" => Some(Self::Png),
+            "jpg" | "jpeg" => Some(Self::Jpeg),
+            "bmp" => Some(Self::Bmp),
+            "tif" | "tiff" => Some(Self::Tiff),
+            "exr" => Some(Self::Exr),
+            "exr-half" => Some(Self::ExrHalf),
+            "hdr" => Some(Self::Hdr),
+            "pfm" => Some(Self::Pfm),
+            _ => None,
+        }
+    }
+
+    pub fn from_path(path: &Path) -> Option<Self> {
+        Self::from_extension(path.extension()?.to_str()?)
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Png => "png",
+            Self::Jpeg => "jpg",
+            Self::Bmp => "bmp",
+            Self::Tiff => "tiff",
+            Self::Exr | Self::ExrHalf => "exr",
+            Self::Hdr => "hdr",
+            Self::Pfm => "pfm",
+        }
+    }
+
+    /// Whether the format stores linear, unclamped radiance rather than 8-bit color.
+    pub fn is_hdr(self) -> bool {
+        matches!(self, Self::Exr | Self::ExrHalf | Self::Hdr | Self::Pfm)
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ExrHalf => f.write_str("exr-half"),
+            _ => f.write_str(self.extension()),
+        }
+    }
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_extension(s).ok_or_else(|| format!("unknown image format '{s}'"))
+    }
+}
+
+/// Saves a `width` by `height` image, stored row by row.
+///
+/// 8-bit formats are gamma corrected and clamped, while HDR formats keep the linear
+/// values as they are.
+///
+/// # Errors
+///
+/// Returns an error if the file can't be written or encoded.
+pub fn save(
+    pixels: &[Color],
+    width: usize,
+    height: usize,
+    path: &Path,
+    format: Format,
+) -> ImageResult<()> {
+    assert_eq!(pixels.len(), width * height);
+    let pixel = |x: usize, y: usize| pixels[y * width + x].0;
+    match format {
+        Format::Png | Format::Jpeg | Format::Bmp | Format::Tiff => {
+            let buf: Vec<_> = pixels.iter().flat_map(Color::bytes).collect();
+            let image_format = match format {
+                Format::Png => ImageFormat::Png,
+                Format::Jpeg => ImageFormat::Jpeg,
+                Format::Bmp => ImageFormat::Bmp,
+                _ => ImageFormat::Tiff,
+            };
+            image::save_buffer_with_format(
+                path,
+                &buf,
+                width as u32,
+                height as u32,
+                ColorType::Rgb8,
+                image_format,
+            )
+        }
+        Format::Exr => exr::prelude::write_rgb_file(path, width, height, |x, y| {
+            let p = pixel(x, y);
+            (p.x, p.y, p.z)
+        })
+        .map_err(exr_error),
+        Format::ExrHalf => exr::prelude::write_rgb_file(path, width, height, |x, y| {
+            let p = pixel(x, y);
+            (f16::from_f32(p.x), f16::from_f32(p.y), f16::from_f32(p.z))
+        })
+        .map_err(exr_error),
+        Format::Hdr => {
+            let image = Rgb32FImage::from_fn(width as u32, height as u32, |x, y| {
+                Rgb(pixel(x as usize, y as usize).to_array())
+            });
+            image.save_with_format(path, ImageFormat::Hdr)
+        }
+        Format::Pfm => {
+            let mut file = BufWriter::new(File::create(path)?);
+            // A negative scale means little-endian, and rows go from the bottom up
+            write!(file, "PF\n{width} {height}\n-1.0\n")?;
+            for y in (0..height).rev() {
+                for x in 0..width {
+                    for channel in pixel(x, y).to_array() {
+                        file.write_all(&channel.to_le_bytes())?;
+                    }
+                }
+            }
+            file.flush()?;
+            Ok(())
+        }
+    }
+}
+
+fn exr_error(error: exr::error::Error) -> ImageError {
+    ImageError::Encoding(EncodingError::new(
+        ImageFormatHint::Exact(ImageFormat::OpenExr),
+        error,
+    ))
+}
+
+/// Copies `path` into `dir` under a name made from the current time, creating `dir`
+/// if needed. Returns the path of the copy.
+///
+/// # Errors
+///
+/// Returns an error if `dir` can't be created or the file can't be copied.
+pub fn archive(path: &Path, dir: &Path) -> io::Result<PathBuf> {
+    fs::create_dir_all(dir)?;
+    let mut name = Local::now().format("%y%m%d_%H%M%S").to_string();
+    if let Some(extension) = path.extension().and_then(|extension| extension.to_str()) {
+        name = format!("{name}.{extension}");
+    }
+    let archived = dir.join(name);
+    fs::copy(path, &archived)?;
+    Ok(archived)
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::Vec3A as Vec3;
+
+    use super::*;
+
+    #[test]
+    fn test_save_by_extension() {
+        assert_eq!(Format::from_path(Path::new("a/b.JPEG")), Some(Format::Jpeg));
+        assert_eq!(Format::from_path(Path::new("render")), None);
+
+        let name = format!("ray-tracing-output-test-{}", std::process::id());
+        let dir = std::env::temp_dir().join(name);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("image.png");
+        let pixels = [Color::new(1.0, 0.0, 0.0), Color::new(0.0, 0.0, 1.0)];
+        save(&pixels, 2, 1, &path, Format::from_path(&path).unwrap()).unwrap();
+
+        let image = image::open(&path).unwrap().to_rgb8();
+        assert_eq!(image.dimensions(), (2, 1));
+        assert_eq!(image.get_pixel(0, 0).0, [255, 0, 0]);
+
+        let archived = archive(&path, &dir.join("archive")).unwrap();
+        assert_eq!(archived.extension().unwrap(), "png");
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_hdr_formats_keep_radiance() {
+        let name = format!("ray-tracing-hdr-test-{}", std::process::id());
+        let dir = std::env::temp_dir().join(name);
+        fs::create_dir_all(&dir).unwrap();
+        let pixels = [Color::new(4.0, 0.5, 0.0), Color::new(0.0, 0.0, 100.0)];
+
+        for (format, tolerance) in [
+            (Format::Exr, 0.0),
+            (Format::ExrHalf, 0.01),
+            (Format::Hdr, 0.05),
+        ] {
+            let path = dir.join(format!("image.{format}"));
+            save(&pixels, 2, 1, &path, format).unwrap();
+            let image = image::ImageReader::open(&path)
+                .unwrap()
+                .with_guessed_format()
+                .unwrap()
+                .decode()
+                .unwrap()
+                .to_rgb32f();
+            for (x, expected) in pixels.iter().enumerate() {
+                let actual = Vec3::from_array(image.get_pixel(x as u32, 0).0);
+                let error = (actual - expected.0).abs().max_element();
+                assert!(error <= tolerance * expected.0.max_element(), "{format}");
+            }
+        }
+
+        let path = dir.join("image.pfm");
+        save(&pixels, 2, 1, &path, Format::Pfm).unwrap();
+        let bytes = fs::read(&path).unwrap();
+        let header = b"PF\n2 1\n-1.0\n";
+        assert_eq!(&bytes[..header.len()], header);
+        assert_eq!(bytes.len(), header.len() + 2 * 3 * 4);
+        assert_eq!(bytes[header.len()..header.len() + 4], 4.0f32.to_le_bytes());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
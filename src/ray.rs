@@ -1,11 +1,22 @@
+use std::hash::Hasher;
+
 use glam::Vec3A as Vec3;
 
+use crate::fingerprint::Fingerprint;
+
 #[derive(Clone, Copy, Debug)]
 pub struct Ray {
     pub origin: Vec3,
     pub direction: Vec3,
 }
 
+impl Fingerprint for Ray {
+    fn fingerprint(&self, hasher: &mut dyn Hasher) {
+        self.origin.fingerprint(hasher);
+        self.direction.fingerprint(hasher);
+    }
+}
+
 impl Ray {
     pub fn new(origin: Vec3, direction: Vec3) -> Self {
         Self { origin, direction }
@@ -1,5 +1,56 @@
+//! Helpers for random numbers. Renders draw theirs from each sample's
+//! [`Samples`](crate::camera::Samples), so images only depend on the seed.
+
 use std::ops::Range;
 
 pub fn random_range(range: &Range<f32>) -> f32 {
     fastrand::f32() * (range.end - range.start) + range.start
 }
+
+/// Combines `values` into one well-mixed number.
+pub(crate) fn hash(values: &[u64]) -> u64 {
+    values.iter().fold(0, |hash, &value| mix(hash ^ value))
+}
+
+/// The `SplitMix64` finalizer, which sends nearby inputs to unrelated outputs.
+fn mix(mut z: u64) -> u64 {
+    z = z.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// A uniformly distributed unit vector drawn from `rng`, for tests that have to give
+/// the same result every run.
+#[cfg(test)]
+fn random_direction(rng: &mut fastrand::Rng) -> glam::Vec3A {
+    let z = 1.0 - 2.0 * rng.f32();
+    let r = (1.0 - z * z).max(0.0).sqrt();
+    let phi = std::f32::consts::TAU * rng.f32();
+    glam::Vec3A::new(r * phi.cos(), r * phi.sin(), z)
+}
+
+/// Checks that `sample`, given numbers in `[0, 1)²`, picks a direction whose pdf
+/// according to `pdf` is the one it returned.
+#[cfg(test)]
+pub(crate) fn assert_sample_matches_pdf(
+    sample: impl Fn(glam::Vec2) -> (glam::Vec3A, f32),
+    pdf: impl Fn(glam::Vec3A) -> f32,
+) {
+    let mut rng = fastrand::Rng::with_seed(1);
+    for _ in 0..100 {
+        let (direction, expected) = sample(glam::Vec2::new(rng.f32(), rng.f32()));
+        assert!((pdf(direction) - expected).abs() <= expected * 1e-3);
+    }
+}
+
+/// Checks that `pdf` integrates to one over all directions, by averaging it over
+/// random ones.
+#[cfg(test)]
+pub(crate) fn assert_pdf_integrates_to_one(pdf: impl Fn(glam::Vec3A) -> f32) {
+    let mut rng = fastrand::Rng::with_seed(1);
+    let n = 200_000;
+    let total: f32 = (0..n).map(|_| pdf(random_direction(&mut rng))).sum();
+    let integral = total / n as f32 * 4.0 * std::f32::consts::PI;
+    assert!((integral - 1.0).abs() < 0.05, "integrates to {integral}");
+}
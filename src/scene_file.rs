@@ -0,0 +1,999 @@
+//! Declarative TOML scenes, as an alternative to building them in code.
+//!
+//! ```toml
+//! bvh = true
+//!
+//! [camera]
+//! samples_per_pixel = 100
+//! look_from = [13.0, 2.0, 3.0]
+//! background = { type = "solid", color = [0.0, 0.0, 0.0] }
+//!
+//! [textures.checker]
+//! type = "checker"
+//! odd = [0.1, 0.01, 0.4]
+//! even = [0.9, 0.9, 0.9]
+//! scale = 0.5
+//!
+//! [materials.ground]
+//! type = "lambertian"
+//! texture = "checker"
+//!
+//! [[objects]]
+//! type = "sphere"
+//! center = [0.0, -1000.0, 0.0]
+//! radius = 1000.0
+//! material = "ground"
+//! ```
+//!
+//! Paths to images and models are relative to the scene file.
+
+use std::{
+    collections::HashMap,
+    error::Error,
+    fmt,
+    fs,
+    io,
+    mem,
+    ops::Range,
+    path::{
+        Path,
+        PathBuf,
+    },
+    sync::Arc,
+    vec,
+};
+
+use glam::{
+    Quat,
+    Vec3A as Vec3,
+};
+use serde::{
+    de::{
+        self,
+        value::MapAccessDeserializer,
+        DeserializeOwned,
+        DeserializeSeed,
+        EnumAccess,
+        IntoDeserializer,
+        MapAccess,
+        Unexpected,
+        VariantAccess,
+        Visitor,
+    },
+    forward_to_deserialize_any,
+    Deserialize,
+    Deserializer,
+};
+use toml::{
+    Spanned,
+    Value,
+};
+
+use crate::{
+    camera::{
+        Background,
+        Builder,
+        Stores,
+    },
+    environment::EnvironmentMap,
+    hittable::{
+        BvhNode,
+        Cuboid,
+        Hittable,
+        HittableList,
+        Quad,
+        Sphere,
+    },
+    material::{
+        Dielectric,
+        DiffuseLight,
+        Lambertian,
+        Material,
+        Metal,
+    },
+    obj,
+    texture::{
+        CheckerTexture,
+        ImageTexture,
+        SolidColor,
+        SurfaceCheckerTexture,
+        TextureHandle,
+    },
+};
+
+#[derive(Debug)]
+pub enum SceneFileError {
+    Io {
+        path: PathBuf,
+        source: io::Error,
+    },
+    Invalid {
+        path: PathBuf,
+        line: usize,
+        column: usize,
+        message: String,
+    },
+}
+
+impl fmt::Display for SceneFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io { path, source } => write!(f, "{}: {source}", path.display()),
+            Self::Invalid {
+                path,
+                line,
+                column,
+                message,
+            } => write!(f, "{}:{line}:{column}: {message}", path.display()),
+        }
+    }
+}
+
+impl Error for SceneFileError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io { source, .. } => Some(source),
+            Self::Invalid { .. } => None,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SceneFile {
+    #[serde(default)]
+    camera: CameraSpec,
+    #[serde(default = "default_bvh")]
+    bvh: bool,
+    // Kept as raw tables so that errors inside them can point back at their location
+    #[serde(default)]
+    textures: HashMap<String, RawTable>,
+    #[serde(default)]
+    materials: HashMap<String, RawTable>,
+    #[serde(default)]
+    objects: Vec<RawTable>,
+}
+
+/// A table with the location of each of its values, picked apart by its `type` key.
+type RawTable = Spanned<HashMap<String, Spanned<Value>>>;
+
+fn default_bvh() -> bool {
+    true
+}
+
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+struct CameraSpec {
+    width: Option<Spanned<usize>>,
+    aspect_ratio: Option<Spanned<f32>>,
+    samples_per_pixel: Option<usize>,
+    max_depth: Option<usize>,
+    /// Minimum depth before Russian roulette may end paths.
+    russian_roulette: Option<usize>,
+    vertical_fov: Option<f32>,
+    look_from: Option<Spanned<[f32; 3]>>,
+    look_at: Option<Spanned<[f32; 3]>>,
+    vup: Option<Spanned<[f32; 3]>>,
+    defocus_angle: Option<f32>,
+    focus_dist: Option<f32>,
+    background: Option<RawTable>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+enum BackgroundSpec {
+    Solid {
+        color: [f32; 3],
+    },
+    Gradient {
+        bottom: [f32; 3],
+        top: [f32; 3],
+    },
+    Texture {
+        texture: String,
+    },
+    Environment {
+        path: PathBuf,
+        #[serde(default)]
+        rotation: f32,
+        #[serde(default = "default_intensity")]
+        intensity: f32,
+    },
+}
+
+fn default_intensity() -> f32 {
+    1.0
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+enum TextureSpec {
+    Solid {
+        color: [f32; 3],
+    },
+    Checker {
+        odd: [f32; 3],
+        even: [f32; 3],
+        scale: f32,
+    },
+    SurfaceChecker {
+        odd: [f32; 3],
+        even: [f32; 3],
+        squares: f32,
+    },
+    Image {
+        path: PathBuf,
+    },
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+enum MaterialSpec {
+    Lambertian {
+        texture: Option<String>,
+        albedo: Option<[f32; 3]>,
+    },
+    Metal {
+        albedo: [f32; 3],
+        #[serde(default)]
+        fuzz: f32,
+    },
+    Dielectric {
+        refraction_index: f32,
+    },
+    DiffuseLight {
+        texture: Option<String>,
+        color: Option<[f32; 3]>,
+    },
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+enum ObjectSpec {
+    Sphere {
+        center: [f32; 3],
+        radius: f32,
+        material: String,
+    },
+    MovingSphere {
+        start: [f32; 3],
+        end: [f32; 3],
+        radius: f32,
+        material: String,
+    },
+    Quad {
+        q: [f32; 3],
+        u: [f32; 3],
+        v: [f32; 3],
+        material: String,
+    },
+    Box {
+        a: [f32; 3],
+        b: [f32; 3],
+        /// Rotation about the vertical axis through the box's center, in degrees.
+        #[serde(default)]
+        rotation: f32,
+        material: String,
+    },
+    Obj {
+        path: PathBuf,
+    },
+}
+
+/// Reads a scene file into a camera builder, ready for command line overrides.
+///
+/// # Errors
+///
+/// Returns an error if the file can't be read, or if it is invalid, with the line and
+/// column of the offending entry.
+pub fn load(path: impl AsRef<Path>) -> Result<Builder, SceneFileError> {
+    let path = path.as_ref();
+    let source = fs::read_to_string(path).map_err(|source| SceneFileError::Io {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    parse(&source, path)
+}
+
+/// Parses the contents of the scene file at `path`.
+///
+/// # Errors
+///
+/// Returns an error if the scene is invalid, with the line and column of the
+/// offending entry.
+pub fn parse(source: &str, path: &Path) -> Result<Builder, SceneFileError> {
+    Loader {
+        source,
+        path,
+        dir: path.parent().unwrap_or(Path::new("")),
+        stores: Stores::default(),
+        textures: HashMap::new(),
+    }
+    .load()
+}
+
+struct Loader<'a> {
+    source: &'a str,
+    path: &'a Path,
+    dir: &'a Path,
+    stores: Stores,
+    textures: HashMap<String, TextureHandle>,
+}
+
+impl Loader<'_> {
+    fn load(mut self) -> Result<Builder, SceneFileError> {
+        let scene: SceneFile = toml::from_str(self.source).map_err(|error| {
+            self.error(
+                error.span().unwrap_or_default(),
+                error.message().to_string(),
+            )
+        })?;
+
+        // Sort so that errors are reported in a stable order
+        let mut textures: Vec<_> = scene.textures.iter().collect();
+        textures.sort_by_key(|(_, table)| table.span().start);
+        for (name, table) in textures {
+            let spec: TextureSpec = self.deserialize(table)?;
+            let handle = self.texture(spec, table)?;
+            self.textures.insert(name.clone(), handle);
+        }
+
+        let mut materials: Vec<_> = scene.materials.iter().collect();
+        materials.sort_by_key(|(_, table)| table.span().start);
+        let materials = materials
+            .into_iter()
+            .map(|(name, table)| {
+                let spec: MaterialSpec = self.deserialize(table)?;
+                self.check_material(&spec, table)?;
+                Ok((name.as_str(), spec))
+            })
+            .collect::<Result<HashMap<_, _>, _>>()?;
+
+        let mut world = HittableList::default();
+        for table in &scene.objects {
+            let spec: ObjectSpec = self.deserialize(table)?;
+            self.add_object(&mut world, spec, &materials, table)?;
+        }
+        if world.objects.is_empty() {
+            return Err(self.error(0..0, "scene has no objects".to_string()));
+        }
+
+        let background = match &scene.camera.background {
+            Some(table) => {
+                let spec = self.deserialize(table)?;
+                Some(self.background(spec, table)?)
+            }
+            None => None,
+        };
+
+        let stores = mem::take(&mut self.stores);
+        let mut builder = if scene.bvh {
+            Builder::new(BvhNode::from_list(world), stores)
+        } else {
+            Builder::new(world, stores)
+        };
+
+        let camera = scene.camera;
+        if let Some(width) = camera.width {
+            if *width.get_ref() == 0 {
+                return Err(self.error(width.span(), "width must be positive".to_string()));
+            }
+            builder = builder.width(width.into_inner());
+        }
+        if let Some(aspect_ratio) = camera.aspect_ratio {
+            let value = *aspect_ratio.get_ref();
+            if !(value.is_finite() && value > 0.0) {
+                let message = format!("aspect_ratio must be positive, found {value}");
+                return Err(self.error(aspect_ratio.span(), message));
+            }
+            builder = builder.aspect_ratio(value);
+        }
+        if let Some(samples_per_pixel) = camera.samples_per_pixel {
+            builder = builder.samples_per_pixel(samples_per_pixel);
+        }
+        if let Some(max_depth) = camera.max_depth {
+            builder = builder.max_depth(max_depth);
+        }
+        if camera.russian_roulette.is_some() {
+            builder = builder.russian_roulette(camera.russian_roulette);
+        }
+        if let Some(vertical_fov) = camera.vertical_fov {
+            builder = builder.vertical_fov(vertical_fov);
+        }
+        if let Some(look_from) = &camera.look_from {
+            builder = builder.look_from(Vec3::from(*look_from.get_ref()));
+        }
+        if let Some(look_at) = &camera.look_at {
+            builder = builder.look_at(Vec3::from(*look_at.get_ref()));
+        }
+        if let Some(vup) = &camera.vup {
+            builder = builder.vup(Vec3::from(*vup.get_ref()));
+        }
+        if builder.is_vup_along_view() {
+            let span = [&camera.vup, &camera.look_at, &camera.look_from]
+                .into_iter()
+                .find_map(|value| value.as_ref().map(Spanned::span))
+                .unwrap_or_default();
+            let message = "vup must not be parallel to the view direction".to_string();
+            return Err(self.error(span, message));
+        }
+        if let Some(defocus_angle) = camera.defocus_angle {
+            builder = builder.defocus_angle(defocus_angle);
+        }
+        if let Some(focus_dist) = camera.focus_dist {
+            builder = builder.focus_dist(focus_dist);
+        }
+        if let Some(background) = background {
+            builder = builder.background(background);
+        }
+        Ok(builder)
+    }
+
+    /// Deserializes a table tagged by its `type` into the matching variant of `T`.
+    fn deserialize<T: DeserializeOwned>(&self, table: &RawTable) -> Result<T, SceneFileError> {
+        T::deserialize(Tagged(table))
+            .map_err(|error| self.error(error.span.unwrap_or_else(|| table.span()), error.message))
+    }
+
+    fn texture(
+        &mut self,
+        spec: TextureSpec,
+        table: &RawTable,
+    ) -> Result<TextureHandle, SceneFileError> {
+        Ok(match spec {
+            TextureSpec::Solid { color } => self
+                .stores
+                .textures
+                .add(SolidColor::new_from_color(color.into())),
+            TextureSpec::Checker { odd, even, scale } => {
+                if scale == 0.0 {
+                    let message = "checker scale must not be 0".to_string();
+                    return Err(self.field_error(table, "scale", message));
+                }
+                self.stores.textures.add(CheckerTexture::new_from_color(
+                    odd.into(),
+                    even.into(),
+                    scale,
+                ))
+            }
+            TextureSpec::SurfaceChecker { odd, even, squares } => {
+                if !(squares.is_finite() && squares > 0.0) {
+                    let message = format!("squares must be positive, found {squares}");
+                    return Err(self.field_error(table, "squares", message));
+                }
+                self.stores
+                    .textures
+                    .add(SurfaceCheckerTexture::new_from_color(
+                        odd.into(),
+                        even.into(),
+                        squares,
+                    ))
+            }
+            TextureSpec::Image { path } => {
+                let path = self.dir.join(path);
+                let texture = ImageTexture::open(&path).map_err(|error| {
+                    self.field_error(
+                        table,
+                        "path",
+                        format!("failed to load image {}: {error}", path.display()),
+                    )
+                })?;
+                self.stores.textures.add(texture)
+            }
+        })
+    }
+
+    fn texture_ref(&self, name: &str, table: &RawTable) -> Result<TextureHandle, SceneFileError> {
+        self.textures
+            .get(name)
+            .copied()
+            .ok_or_else(|| self.field_error(table, "texture", format!("unknown texture '{name}'")))
+    }
+
+    fn check_material(&self, spec: &MaterialSpec, table: &RawTable) -> Result<(), SceneFileError> {
+        match spec {
+            MaterialSpec::Lambertian { texture, albedo } => {
+                self.check_texture_or_color(texture.as_deref(), albedo.is_some(), "albedo", table)
+            }
+            MaterialSpec::DiffuseLight { texture, color } => {
+                self.check_texture_or_color(texture.as_deref(), color.is_some(), "color", table)
+            }
+            MaterialSpec::Metal { fuzz, .. } if !(0.0..=1.0).contains(fuzz) => {
+                let message = format!("fuzz must be between 0 and 1, found {fuzz}");
+                Err(self.field_error(table, "fuzz", message))
+            }
+            MaterialSpec::Dielectric { refraction_index }
+                if !(refraction_index.is_finite() && *refraction_index > 0.0) =>
+            {
+                let message =
+                    format!("refraction_index must be positive, found {refraction_index}");
+                Err(self.field_error(table, "refraction_index", message))
+            }
+            MaterialSpec::Metal { .. } | MaterialSpec::Dielectric { .. } => Ok(()),
+        }
+    }
+
+    fn check_texture_or_color(
+        &self,
+        texture: Option<&str>,
+        has_color: bool,
+        color_field: &str,
+        table: &RawTable,
+    ) -> Result<(), SceneFileError> {
+        match (texture, has_color) {
+            (Some(name), false) => self.texture_ref(name, table).map(|_| ()),
+            (None, true) => Ok(()),
+            _ => Err(self.error(
+                table.span(),
+                format!("expected exactly one of `texture` or `{color_field}`"),
+            )),
+        }
+    }
+
+    fn add_object(
+        &mut self,
+        world: &mut HittableList,
+        spec: ObjectSpec,
+        materials: &HashMap<&str, MaterialSpec>,
+        table: &RawTable,
+    ) -> Result<(), SceneFileError> {
+        match spec {
+            ObjectSpec::Sphere {
+                center,
+                radius,
+                material: name,
+            } => {
+                self.check_radius(radius, table)?;
+                let spec = self.material_spec(materials, &name, table)?;
+                let material = self.material(spec);
+                let sphere = Sphere::new_static(center.into(), radius, material);
+                self.add(world, sphere, spec);
+            }
+            ObjectSpec::MovingSphere {
+                start,
+                end,
+                radius,
+                material: name,
+            } => {
+                self.check_radius(radius, table)?;
+                let spec = self.material_spec(materials, &name, table)?;
+                let material = self.material(spec);
+                let sphere = Sphere::new_start_end(start.into(), end.into(), radius, material);
+                self.add(world, sphere, spec);
+            }
+            ObjectSpec::Quad {
+                q,
+                u,
+                v,
+                material: name,
+            } => {
+                if Vec3::from(u).cross(Vec3::from(v)).length_squared() == 0.0 {
+                    let message = "quad edges must not be parallel".to_string();
+                    return Err(self.field_error(table, "v", message));
+                }
+                let spec = self.material_spec(materials, &name, table)?;
+                let material = self.material(spec);
+                let quad = Quad::new(q.into(), u.into(), v.into(), material);
+                self.add(world, quad, spec);
+            }
+            ObjectSpec::Box {
+                a,
+                b,
+                rotation,
+                material: name,
+            } => {
+                let (a, b) = (Vec3::from(a), Vec3::from(b));
+                if (a - b).abs().min_element() == 0.0 {
+                    return Err(self.field_error(table, "b", "box must not be flat".to_string()));
+                }
+                let spec = self.material_spec(materials, &name, table)?;
+                let material = self.material(spec);
+                let cuboid = Cuboid::oriented(
+                    (a + b) * 0.5,
+                    (b - a).abs() * 0.5,
+                    Quat::from_rotation_y(rotation.to_radians()),
+                    material,
+                );
+                self.add(world, cuboid, spec);
+            }
+            ObjectSpec::Obj { path } => {
+                let meshes = obj::load(self.dir.join(path), &mut self.stores)
+                    .map_err(|error| self.field_error(table, "path", error.to_string()))?;
+                for mesh in meshes {
+                    world.add(mesh);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn material_spec<'m>(
+        &self,
+        materials: &'m HashMap<&str, MaterialSpec>,
+        name: &str,
+        table: &RawTable,
+    ) -> Result<&'m MaterialSpec, SceneFileError> {
+        materials.get(name).ok_or_else(|| {
+            self.field_error(table, "material", format!("unknown material '{name}'"))
+        })
+    }
+
+    /// Adds `object` to the world, and shares it with the lights if its material
+    /// emits.
+    fn add(
+        &mut self,
+        world: &mut HittableList,
+        object: impl Hittable + 'static,
+        spec: &MaterialSpec,
+    ) {
+        if matches!(spec, MaterialSpec::DiffuseLight { .. }) {
+            let object = Arc::new(object);
+            self.stores.lights.add(object.clone());
+            world.add(object);
+        } else {
+            world.add(object);
+        }
+    }
+
+    fn check_radius(&self, radius: f32, table: &RawTable) -> Result<(), SceneFileError> {
+        if radius > 0.0 {
+            Ok(())
+        } else {
+            Err(self.field_error(
+                table,
+                "radius",
+                format!("radius must be positive, found {radius}"),
+            ))
+        }
+    }
+
+    /// Builds a fresh material, which must already have passed `check_material`.
+    fn material(&mut self, spec: &MaterialSpec) -> Box<dyn Material> {
+        let mut texture = |name: &Option<String>, color: &Option<[f32; 3]>| match (name, color) {
+            (Some(name), _) => self.textures[name],
+            (None, Some(color)) => self
+                .stores
+                .textures
+                .add(SolidColor::new_from_color((*color).into())),
+            (None, None) => unreachable!(),
+        };
+
+        match spec {
+            MaterialSpec::Lambertian {
+                texture: name,
+                albedo,
+            } => Box::new(Lambertian::new(texture(name, albedo))),
+            MaterialSpec::Metal { albedo, fuzz } => Box::new(Metal::new((*albedo).into(), *fuzz)),
+            MaterialSpec::Dielectric { refraction_index } => {
+                Box::new(Dielectric::new(*refraction_index))
+            }
+            MaterialSpec::DiffuseLight {
+                texture: name,
+                color,
+            } => Box::new(DiffuseLight::new(texture(name, color))),
+        }
+    }
+
+    fn background(
+        &self,
+        spec: BackgroundSpec,
+        table: &RawTable,
+    ) -> Result<Background, SceneFileError> {
+        Ok(match spec {
+            BackgroundSpec::Solid { color } => Background::Solid(color.into()),
+            BackgroundSpec::Gradient { bottom, top } => Background::Gradient {
+                bottom: bottom.into(),
+                top: top.into(),
+            },
+            BackgroundSpec::Texture { texture } => {
+                Background::Texture(self.texture_ref(&texture, table)?)
+            }
+            BackgroundSpec::Environment {
+                path,
+                rotation,
+                intensity,
+            } => {
+                let path = self.dir.join(path);
+                let environment = EnvironmentMap::open(&path).map_err(|error| {
+                    self.field_error(
+                        table,
+                        "path",
+                        format!("failed to load environment {}: {error}", path.display()),
+                    )
+                })?;
+                Background::Environment(environment.rotation(rotation).intensity(intensity))
+            }
+        })
+    }
+
+    /// An error at the value of `key` in `table`, or at the table if it doesn't have one.
+    fn field_error(&self, table: &RawTable, key: &str, message: String) -> SceneFileError {
+        let span = table
+            .get_ref()
+            .get(key)
+            .map_or_else(|| table.span(), Spanned::span);
+        self.error(span, message)
+    }
+
+    fn error(&self, span: Range<usize>, message: String) -> SceneFileError {
+        let before = &self.source[..span.start.min(self.source.len())];
+        let line = before.matches('\n').count() + 1;
+        let column = before.len() - before.rfind('\n').map_or(0, |i| i + 1) + 1;
+        SceneFileError::Invalid {
+            path: self.path.to_path_buf(),
+            line,
+            column,
+            message,
+        }
+    }
+}
+
+/// Deserializes a raw table as the enum variant named by its `type`, with the rest of
+/// its keys as the variant's fields.
+///
+/// Going through toml's own deserializer would lose the locations, since they aren't
+/// kept by `Value`, so this tracks which value each error came from instead.
+struct Tagged<'a>(&'a RawTable);
+
+/// The fields of a [`Tagged`] table, in the order they appear in the file.
+struct Fields<'a> {
+    fields: vec::IntoIter<(&'a String, &'a Spanned<Value>)>,
+    value: Option<&'a Spanned<Value>>,
+}
+
+#[derive(Debug)]
+struct SpannedError {
+    span: Option<Range<usize>>,
+    message: String,
+}
+
+impl SpannedError {
+    fn at(self, span: Range<usize>) -> Self {
+        Self {
+            span: self.span.or(Some(span)),
+            ..self
+        }
+    }
+}
+
+impl fmt::Display for SpannedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl Error for SpannedError {}
+
+impl de::Error for SpannedError {
+    fn custom<T: fmt::Display>(message: T) -> Self {
+        Self {
+            span: None,
+            message: message.to_string(),
+        }
+    }
+}
+
+impl<'de> Deserializer<'de> for Tagged<'_> {
+    type Error = SpannedError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SpannedError> {
+        visitor.visit_enum(self)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes
+        byte_buf option unit unit_struct newtype_struct seq tuple tuple_struct map
+        struct enum identifier ignored_any
+    }
+}
+
+impl<'de, 'a> EnumAccess<'de> for Tagged<'a> {
+    type Error = SpannedError;
+    type Variant = Fields<'a>;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, Fields<'a>), SpannedError> {
+        let table = self.0;
+        let Some(kind) = table.get_ref().get("type") else {
+            return Err(de::Error::missing_field("type"));
+        };
+        let Value::String(name) = kind.get_ref() else {
+            return Err(SpannedError {
+                span: Some(kind.span()),
+                message: "expected a string".to_string(),
+            });
+        };
+        let variant = seed
+            .deserialize(name.as_str().into_deserializer())
+            .map_err(|error: SpannedError| error.at(kind.span()))?;
+
+        let mut fields: Vec<_> = table
+            .get_ref()
+            .iter()
+            .filter(|(key, _)| *key != "type")
+            .collect();
+        fields.sort_by_key(|(_, value)| value.span().start);
+        let fields = Fields {
+            fields: fields.into_iter(),
+            value: None,
+        };
+        Ok((variant, fields))
+    }
+}
+
+impl<'de> VariantAccess<'de> for Fields<'_> {
+    type Error = SpannedError;
+
+    fn unit_variant(self) -> Result<(), SpannedError> {
+        Err(de::Error::invalid_type(Unexpected::Map, &"unit variant"))
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(
+        self,
+        seed: T,
+    ) -> Result<T::Value, SpannedError> {
+        seed.deserialize(MapAccessDeserializer::new(self))
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        _visitor: V,
+    ) -> Result<V::Value, SpannedError> {
+        Err(de::Error::invalid_type(Unexpected::Map, &"tuple variant"))
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, SpannedError> {
+        visitor.visit_map(self)
+    }
+}
+
+impl<'de> MapAccess<'de> for Fields<'_> {
+    type Error = SpannedError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, SpannedError> {
+        let Some((key, value)) = self.fields.next() else {
+            return Ok(None);
+        };
+        self.value = Some(value);
+        seed.deserialize(key.as_str().into_deserializer())
+            .map(Some)
+            .map_err(|error: SpannedError| error.at(value.span()))
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(
+        &mut self,
+        seed: V,
+    ) -> Result<V::Value, SpannedError> {
+        let value = self
+            .value
+            .take()
+            .expect("a value is only asked for after its key");
+        seed.deserialize(value.get_ref().clone())
+            .map_err(|error| SpannedError {
+                span: Some(value.span()),
+                message: error.message().to_string(),
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(source: &str) -> String {
+        match parse(source, Path::new("test.toml")) {
+            Ok(_) => panic!("expected an error"),
+            Err(error) => error.to_string(),
+        }
+    }
+
+    const SPHERE: &str = r#"
+[materials.red]
+type = "lambertian"
+albedo = [1.0, 0.0, 0.0]
+
+[[objects]]
+type = "sphere"
+center = [0.0, 0.0, 0.0]
+radius = 1.0
+material = "red"
+"#;
+
+    #[test]
+    fn test_valid_scene() {
+        assert!(parse(SPHERE, Path::new("test.toml")).is_ok());
+    }
+
+    #[test]
+    fn test_unknown_material() {
+        let source = SPHERE.replace("material = \"red\"", "material = \"blue\"");
+        assert_eq!(error(&source), "test.toml:10:12: unknown material 'blue'");
+    }
+
+    #[test]
+    fn test_unknown_field() {
+        let source = SPHERE.replace("radius", "raduis");
+        assert_eq!(
+            error(&source),
+            "test.toml:9:10: unknown field `raduis`, expected one of `center`, `radius`, \
+             `material`"
+        );
+    }
+
+    #[test]
+    fn test_invalid_value() {
+        let source = SPHERE.replace("radius = 1.0", "radius = \"big\"");
+        assert_eq!(
+            error(&source),
+            "test.toml:9:10: invalid type: string \"big\", expected f32"
+        );
+
+        let source = SPHERE.replace("\"sphere\"", "\"ball\"");
+        assert!(error(&source).starts_with("test.toml:7:8: unknown variant `ball`"));
+    }
+
+    #[test]
+    fn test_missing_field() {
+        let source = SPHERE.replace("radius = 1.0\n", "");
+        assert_eq!(error(&source), "test.toml:6:1: missing field `radius`");
+
+        let source = SPHERE.replace("type = \"sphere\"\n", "");
+        assert_eq!(error(&source), "test.toml:6:1: missing field `type`");
+    }
+
+    #[test]
+    fn test_syntax_error() {
+        let source = SPHERE.replace("radius = 1.0", "radius = ");
+        assert!(error(&source).starts_with("test.toml:9:10: "));
+    }
+
+    #[test]
+    fn test_emitters_are_lights() {
+        let source = format!(
+            "{SPHERE}
+[materials.lamp]
+type = \"diffuse_light\"
+color = [4.0, 4.0, 4.0]
+
+[[objects]]
+type = \"box\"
+a = [0.0, 2.0, 0.0]
+b = [1.0, 3.0, 1.0]
+material = \"lamp\"
+"
+        );
+        let camera = parse(&source, Path::new("test.toml")).unwrap().build();
+        assert_eq!(camera.stores().lights.objects.len(), 1);
+    }
+
+    #[test]
+    fn test_out_of_range_values() {
+        let source = SPHERE.replace(
+            "type = \"lambertian\"\nalbedo = [1.0, 0.0, 0.0]",
+            "type = \"dielectric\"\nrefraction_index = 0.0",
+        );
+        assert_eq!(
+            error(&source),
+            "test.toml:4:20: refraction_index must be positive, found 0"
+        );
+
+        let source = format!("[camera]\nwidth = 0\n{SPHERE}");
+        assert_eq!(error(&source), "test.toml:2:9: width must be positive");
+
+        let source = format!("[camera]\nlook_at = [0.0, -1.0, 0.0]\n{SPHERE}");
+        assert_eq!(
+            error(&source),
+            "test.toml:2:11: vup must not be parallel to the view direction"
+        );
+    }
+}
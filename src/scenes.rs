@@ -1,23 +1,43 @@
-use std::path::Path;
+use std::{
+    path::Path,
+    sync::Arc,
+};
 
-use glam::Vec3A as Vec3;
+use glam::{
+    Affine3A,
+    Quat,
+    Vec2,
+    Vec3A as Vec3,
+};
 use ray_tracing::{
     camera::{
+        Background,
         Builder,
         Stores,
     },
-    color::Color,
+    color::{
+        Color,
+        BLACK,
+    },
     extension_traits::Vec3Ext,
     hittable::{
         BvhNode,
+        ConstantMedium,
+        Cuboid,
         HittableList,
+        Instance,
+        Quad,
         Sphere,
+        TriangleMesh,
     },
     material::{
         Dielectric,
+        DiffuseLight,
+        Isotropic,
         Lambertian,
         Metal,
     },
+    obj,
     rng::random_range,
     texture::{
         CheckerTexture,
@@ -241,3 +261,407 @@ pub fn world() -> Builder {
         .vup(Vec3::Y)
         .defocus_angle(0.0)
 }
+
+pub fn quads() -> Builder {
+    let mut world = HittableList::default();
+    let mut stores = Stores::default();
+
+    let left_red = stores.textures.add(SolidColor::new(1.0, 0.2, 0.2));
+    let back_green = stores.textures.add(SolidColor::new(0.2, 1.0, 0.2));
+    let right_blue = stores.textures.add(SolidColor::new(0.2, 0.2, 1.0));
+    let upper_orange = stores.textures.add(SolidColor::new(1.0, 0.5, 0.0));
+    let lower_teal = stores.textures.add(SolidColor::new(0.2, 0.8, 0.8));
+
+    world.add(Quad::new(
+        Vec3::new(-3.0, -2.0, 5.0),
+        Vec3::new(0.0, 0.0, -4.0),
+        Vec3::new(0.0, 4.0, 0.0),
+        Lambertian::new(left_red),
+    ));
+    world.add(Quad::new(
+        Vec3::new(-2.0, -2.0, 0.0),
+        Vec3::new(4.0, 0.0, 0.0),
+        Vec3::new(0.0, 4.0, 0.0),
+        Lambertian::new(back_green),
+    ));
+    world.add(Quad::new(
+        Vec3::new(3.0, -2.0, 1.0),
+        Vec3::new(0.0, 0.0, 4.0),
+        Vec3::new(0.0, 4.0, 0.0),
+        Lambertian::new(right_blue),
+    ));
+    world.add(Quad::new(
+        Vec3::new(-2.0, 3.0, 1.0),
+        Vec3::new(4.0, 0.0, 0.0),
+        Vec3::new(0.0, 0.0, 4.0),
+        Lambertian::new(upper_orange),
+    ));
+    world.add(Quad::new(
+        Vec3::new(-2.0, -3.0, 5.0),
+        Vec3::new(4.0, 0.0, 0.0),
+        Vec3::new(0.0, 0.0, -4.0),
+        Lambertian::new(lower_teal),
+    ));
+
+    let bvh = BvhNode::from_list(world);
+
+    Builder::new(bvh, stores)
+        .aspect_ratio(1.0)
+        .vertical_fov(80.0)
+        .look_from(Vec3::new(0.0, 0.0, 9.0))
+        .look_at(Vec3::ZERO)
+        .vup(Vec3::Y)
+        .defocus_angle(0.0)
+}
+
+pub fn mesh() -> Builder {
+    let mut world = HittableList::default();
+    let mut stores = Stores::default();
+
+    let size = 200;
+    let extent = 10.0;
+    let height = |x: f32, z: f32| 0.3 * x.sin() * z.cos();
+
+    let mut positions = Vec::new();
+    let mut normals = Vec::new();
+    let mut uvs = Vec::new();
+    for i in 0..=size {
+        for j in 0..=size {
+            let u = i as f32 / size as f32;
+            let v = j as f32 / size as f32;
+            let x = (u - 0.5) * extent;
+            let z = (v - 0.5) * extent;
+            positions.push(Vec3::new(x, height(x, z), z));
+            let dx = 0.3 * x.cos() * z.cos();
+            let dz = -0.3 * x.sin() * z.sin();
+            normals.push(Vec3::new(-dx, 1.0, -dz));
+            uvs.push(Vec2::new(u, v));
+        }
+    }
+
+    let mut indices = Vec::new();
+    let stride = size as u32 + 1;
+    for i in 0..size as u32 {
+        for j in 0..size as u32 {
+            let a = i * stride + j;
+            let b = a + stride;
+            indices.push([a, a + 1, b]);
+            indices.push([b, a + 1, b + 1]);
+        }
+    }
+
+    let checker_texture = stores.textures.add(SurfaceCheckerTexture::new(
+        SolidColor::new(0.1, 0.01, 0.4),
+        SolidColor::new(0.9, 0.9, 0.9),
+        20.0,
+    ));
+    world.add(TriangleMesh::new(
+        positions,
+        indices,
+        Some(normals),
+        Some(uvs),
+        Lambertian::new(checker_texture),
+    ));
+
+    let material = Metal::new(Color::new(0.90, 0.90, 1.0), 0.0);
+    world.add(Sphere::new_static(Vec3::new(0.0, 1.2, 0.0), 1.0, material));
+
+    let bvh = BvhNode::from_list(world);
+
+    Builder::new(bvh, stores)
+        .vertical_fov(30.0)
+        .look_from(Vec3::new(10.0, 5.0, 10.0))
+        .look_at(Vec3::ZERO)
+        .vup(Vec3::Y)
+        .defocus_angle(0.0)
+}
+
+pub fn cube() -> Builder {
+    let mut world = HittableList::default();
+    let mut stores = Stores::default();
+
+    for mesh in obj::load("models/cube.obj", &mut stores).unwrap() {
+        world.add(mesh);
+    }
+
+    let checker_texture = stores.textures.add(CheckerTexture::new(
+        SolidColor::new(0.1, 0.01, 0.4),
+        SolidColor::new(0.9, 0.9, 0.9),
+        0.5,
+    ));
+    world.add(Sphere::new_static(
+        Vec3::new(0.0, -1001.0, 0.0),
+        1000.0,
+        Lambertian::new(checker_texture),
+    ));
+
+    let bvh = BvhNode::from_list(world);
+
+    Builder::new(bvh, stores)
+        .vertical_fov(30.0)
+        .look_from(Vec3::new(5.0, 3.0, 6.0))
+        .look_at(Vec3::ZERO)
+        .vup(Vec3::Y)
+        .defocus_angle(0.0)
+}
+
+pub fn simple_light() -> Builder {
+    let mut world = HittableList::default();
+    let mut stores = Stores::default();
+
+    let checker_texture = stores.textures.add(CheckerTexture::new(
+        SolidColor::new(0.1, 0.01, 0.4),
+        SolidColor::new(0.9, 0.9, 0.9),
+        0.5,
+    ));
+    world.add(Sphere::new_static(
+        Vec3::new(0.0, -1000.0, 0.0),
+        1000.0,
+        Lambertian::new(checker_texture),
+    ));
+    world.add(Sphere::new_static(
+        Vec3::new(0.0, 2.0, 0.0),
+        2.0,
+        Lambertian::new(checker_texture),
+    ));
+
+    let light_texture = stores.textures.add(SolidColor::new(4.0, 4.0, 4.0));
+    let quad_light = |texture| {
+        Quad::new(
+            Vec3::new(3.0, 1.0, -2.0),
+            Vec3::new(2.0, 0.0, 0.0),
+            Vec3::new(0.0, 2.0, 0.0),
+            DiffuseLight::new(texture),
+        )
+    };
+    let sphere_light =
+        |texture| Sphere::new_static(Vec3::new(0.0, 7.0, 0.0), 2.0, DiffuseLight::new(texture));
+    world.add(quad_light(light_texture));
+    world.add(sphere_light(light_texture));
+    stores.lights.add(quad_light(light_texture));
+    stores.lights.add(sphere_light(light_texture));
+
+    let bvh = BvhNode::from_list(world);
+
+    Builder::new(bvh, stores)
+        .samples_per_pixel(500)
+        .vertical_fov(20.0)
+        .look_from(Vec3::new(26.0, 3.0, 6.0))
+        .look_at(Vec3::new(0.0, 2.0, 0.0))
+        .vup(Vec3::Y)
+        .defocus_angle(0.0)
+        .background(Background::Solid(BLACK))
+}
+
+pub fn cornell_box() -> Builder {
+    let mut world = HittableList::default();
+    let mut stores = Stores::default();
+
+    let red = stores.textures.add(SolidColor::new(0.65, 0.05, 0.05));
+    let white = stores.textures.add(SolidColor::new(0.73, 0.73, 0.73));
+    let green = stores.textures.add(SolidColor::new(0.12, 0.45, 0.15));
+    let light = stores.textures.add(SolidColor::new(15.0, 15.0, 15.0));
+
+    world.add(Quad::new(
+        Vec3::new(555.0, 0.0, 0.0),
+        Vec3::new(0.0, 555.0, 0.0),
+        Vec3::new(0.0, 0.0, 555.0),
+        Lambertian::new(green),
+    ));
+    world.add(Quad::new(
+        Vec3::new(0.0, 0.0, 0.0),
+        Vec3::new(0.0, 555.0, 0.0),
+        Vec3::new(0.0, 0.0, 555.0),
+        Lambertian::new(red),
+    ));
+    let ceiling_light = || {
+        Quad::new(
+            Vec3::new(343.0, 554.0, 332.0),
+            Vec3::new(-130.0, 0.0, 0.0),
+            Vec3::new(0.0, 0.0, -105.0),
+            DiffuseLight::new(light),
+        )
+    };
+    world.add(ceiling_light());
+    stores.lights.add(ceiling_light());
+    world.add(Quad::new(
+        Vec3::new(0.0, 0.0, 0.0),
+        Vec3::new(555.0, 0.0, 0.0),
+        Vec3::new(0.0, 0.0, 555.0),
+        Lambertian::new(white),
+    ));
+    world.add(Quad::new(
+        Vec3::new(555.0, 555.0, 555.0),
+        Vec3::new(-555.0, 0.0, 0.0),
+        Vec3::new(0.0, 0.0, -555.0),
+        Lambertian::new(white),
+    ));
+    world.add(Quad::new(
+        Vec3::new(0.0, 0.0, 555.0),
+        Vec3::new(555.0, 0.0, 0.0),
+        Vec3::new(0.0, 555.0, 0.0),
+        Lambertian::new(white),
+    ));
+
+    let rotation = Quat::from_rotation_y(15f32.to_radians());
+    let half_extents = Vec3::new(82.5, 165.0, 82.5);
+    world.add(Cuboid::oriented(
+        rotation * half_extents + Vec3::new(265.0, 0.0, 295.0),
+        half_extents,
+        rotation,
+        Lambertian::new(white),
+    ));
+
+    let rotation = Quat::from_rotation_y(-18f32.to_radians());
+    let half_extents = Vec3::new(82.5, 82.5, 82.5);
+    world.add(Cuboid::oriented(
+        rotation * half_extents + Vec3::new(130.0, 0.0, 65.0),
+        half_extents,
+        rotation,
+        Lambertian::new(white),
+    ));
+
+    let bvh = BvhNode::from_list(world);
+
+    Builder::new(bvh, stores)
+        .width(600)
+        .aspect_ratio(1.0)
+        .samples_per_pixel(200)
+        .vertical_fov(40.0)
+        .look_from(Vec3::new(278.0, 278.0, -800.0))
+        .look_at(Vec3::new(278.0, 278.0, 0.0))
+        .vup(Vec3::Y)
+        .defocus_angle(0.0)
+        .background(Background::Solid(BLACK))
+}
+
+pub fn instances() -> Builder {
+    let mut world = HittableList::default();
+    let mut stores = Stores::default();
+
+    let mut cube = HittableList::default();
+    for mesh in obj::load("models/cube.obj", &mut stores).unwrap() {
+        cube.add(mesh);
+    }
+    let cube = Arc::new(cube);
+
+    let mut instances = HittableList::default();
+    for a in -15..15 {
+        for b in -15..15 {
+            let scale = random_range(&(0.1..0.3));
+            let axis = Vec3::random_unit_vector();
+            let instance = Instance::new(cube.clone(), Affine3A::IDENTITY)
+                .scale(Vec3::splat(scale))
+                .rotate(axis, random_range(&(0.0..360.0)))
+                .translate(Vec3::new(a as f32, scale * 1.8, b as f32));
+            instances.add(instance);
+        }
+    }
+    world.add(BvhNode::from_list(instances));
+
+    let checker_texture = stores.textures.add(CheckerTexture::new(
+        SolidColor::new(0.1, 0.01, 0.4),
+        SolidColor::new(0.9, 0.9, 0.9),
+        0.5,
+    ));
+    world.add(Sphere::new_static(
+        Vec3::new(0.0, -1000.0, 0.0),
+        1000.0,
+        Lambertian::new(checker_texture),
+    ));
+
+    let bvh = BvhNode::from_list(world);
+
+    Builder::new(bvh, stores)
+        .vertical_fov(30.0)
+        .look_from(Vec3::new(13.0, 4.0, 10.0))
+        .look_at(Vec3::ZERO)
+        .vup(Vec3::Y)
+        .defocus_angle(0.0)
+}
+
+pub fn cornell_smoke() -> Builder {
+    let mut world = HittableList::default();
+    let mut stores = Stores::default();
+
+    let red = stores.textures.add(SolidColor::new(0.65, 0.05, 0.05));
+    let white = stores.textures.add(SolidColor::new(0.73, 0.73, 0.73));
+    let green = stores.textures.add(SolidColor::new(0.12, 0.45, 0.15));
+    let light = stores.textures.add(SolidColor::new(7.0, 7.0, 7.0));
+    let smoke = stores.textures.add(SolidColor::new(0.0, 0.0, 0.0));
+    let fog = stores.textures.add(SolidColor::new(1.0, 1.0, 1.0));
+
+    world.add(Quad::new(
+        Vec3::new(555.0, 0.0, 0.0),
+        Vec3::new(0.0, 555.0, 0.0),
+        Vec3::new(0.0, 0.0, 555.0),
+        Lambertian::new(green),
+    ));
+    world.add(Quad::new(
+        Vec3::new(0.0, 0.0, 0.0),
+        Vec3::new(0.0, 555.0, 0.0),
+        Vec3::new(0.0, 0.0, 555.0),
+        Lambertian::new(red),
+    ));
+    let ceiling_light = || {
+        Quad::new(
+            Vec3::new(113.0, 554.0, 127.0),
+            Vec3::new(330.0, 0.0, 0.0),
+            Vec3::new(0.0, 0.0, 305.0),
+            DiffuseLight::new(light),
+        )
+    };
+    world.add(ceiling_light());
+    stores.lights.add(ceiling_light());
+    world.add(Quad::new(
+        Vec3::new(0.0, 555.0, 0.0),
+        Vec3::new(555.0, 0.0, 0.0),
+        Vec3::new(0.0, 0.0, 555.0),
+        Lambertian::new(white),
+    ));
+    world.add(Quad::new(
+        Vec3::new(0.0, 0.0, 0.0),
+        Vec3::new(555.0, 0.0, 0.0),
+        Vec3::new(0.0, 0.0, 555.0),
+        Lambertian::new(white),
+    ));
+    world.add(Quad::new(
+        Vec3::new(0.0, 0.0, 555.0),
+        Vec3::new(555.0, 0.0, 0.0),
+        Vec3::new(0.0, 555.0, 0.0),
+        Lambertian::new(white),
+    ));
+
+    let rotation = Quat::from_rotation_y(15f32.to_radians());
+    let half_extents = Vec3::new(82.5, 165.0, 82.5);
+    let tall_box = Cuboid::oriented(
+        rotation * half_extents + Vec3::new(265.0, 0.0, 295.0),
+        half_extents,
+        rotation,
+        Lambertian::new(white),
+    );
+    world.add(ConstantMedium::new(tall_box, 0.01, Isotropic::new(smoke)));
+
+    let rotation = Quat::from_rotation_y(-18f32.to_radians());
+    let half_extents = Vec3::new(82.5, 82.5, 82.5);
+    let short_box = Cuboid::oriented(
+        rotation * half_extents + Vec3::new(130.0, 0.0, 65.0),
+        half_extents,
+        rotation,
+        Lambertian::new(white),
+    );
+    world.add(ConstantMedium::new(short_box, 0.01, Isotropic::new(fog)));
+
+    let bvh = BvhNode::from_list(world);
+
+    Builder::new(bvh, stores)
+        .width(600)
+        .aspect_ratio(1.0)
+        .samples_per_pixel(200)
+        .vertical_fov(40.0)
+        .look_from(Vec3::new(278.0, 278.0, -800.0))
+        .look_at(Vec3::new(278.0, 278.0, 0.0))
+        .vup(Vec3::Y)
+        .defocus_angle(0.0)
+        .background(Background::Solid(BLACK))
+}
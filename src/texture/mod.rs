@@ -1,6 +1,13 @@
 use std::{
-    fmt::Debug,
-    path::PathBuf,
+    fmt::{
+        self,
+        Debug,
+    },
+    hash::Hasher,
+    path::{
+        Path,
+        PathBuf,
+    },
 };
 
 use glam::{
@@ -8,16 +15,19 @@ use glam::{
     Vec3A as Vec3,
 };
 
-use crate::color::Color;
+use crate::{
+    color::Color,
+    fingerprint::Fingerprint,
+};
 
-pub trait Texture: Sync + Debug {
+pub trait Texture: Sync + Debug + Fingerprint {
     fn value(&self, uv: Vec2, point: Vec3) -> Color;
 }
 
 #[derive(Debug, Clone, Copy)]
 pub struct TextureHandle(usize);
 
-#[derive(Default)]
+#[derive(Debug, Default)]
 pub struct TextureStore(Vec<Box<dyn Texture>>);
 
 impl TextureStore {
@@ -32,6 +42,18 @@ impl TextureStore {
     }
 }
 
+impl Fingerprint for TextureHandle {
+    fn fingerprint(&self, hasher: &mut dyn Hasher) {
+        self.0.fingerprint(hasher);
+    }
+}
+
+impl Fingerprint for TextureStore {
+    fn fingerprint(&self, hasher: &mut dyn Hasher) {
+        self.0.fingerprint(hasher);
+    }
+}
+
 #[derive(Debug)]
 pub struct SolidColor {
     pub albedo: Color,
@@ -49,6 +71,13 @@ impl SolidColor {
     }
 }
 
+impl Fingerprint for SolidColor {
+    fn fingerprint(&self, hasher: &mut dyn Hasher) {
+        "SolidColor".fingerprint(hasher);
+        self.albedo.fingerprint(hasher);
+    }
+}
+
 impl Texture for SolidColor {
     fn value(&self, _uv: Vec2, _point: Vec3) -> Color {
         self.albedo
@@ -80,6 +109,15 @@ impl CheckerTexture {
     }
 }
 
+impl Fingerprint for CheckerTexture {
+    fn fingerprint(&self, hasher: &mut dyn Hasher) {
+        "CheckerTexture".fingerprint(hasher);
+        self.odd.fingerprint(hasher);
+        self.even.fingerprint(hasher);
+        self.inv_scale.fingerprint(hasher);
+    }
+}
+
 impl Texture for CheckerTexture {
     fn value(&self, uv: Vec2, point: Vec3) -> Color {
         let x = (point.x * self.inv_scale).floor() as i32;
@@ -119,6 +157,15 @@ impl SurfaceCheckerTexture {
     }
 }
 
+impl Fingerprint for SurfaceCheckerTexture {
+    fn fingerprint(&self, hasher: &mut dyn Hasher) {
+        "SurfaceCheckerTexture".fingerprint(hasher);
+        self.odd.fingerprint(hasher);
+        self.even.fingerprint(hasher);
+        self.squares.fingerprint(hasher);
+    }
+}
+
 impl Texture for SurfaceCheckerTexture {
     fn value(&self, uv: Vec2, point: Vec3) -> Color {
         let u = (uv.x * self.squares).floor() as i32;
@@ -132,17 +179,40 @@ impl Texture for SurfaceCheckerTexture {
     }
 }
 
-#[derive(Debug)]
 pub struct ImageTexture {
     image: image::RgbImage,
 }
 
+impl fmt::Debug for ImageTexture {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ImageTexture")
+            .field("width", &self.image.width())
+            .field("height", &self.image.height())
+            .finish_non_exhaustive()
+    }
+}
+
 impl ImageTexture {
     pub fn new(path: impl Into<PathBuf>) -> Self {
-        let path = path.into();
-        let image = image::open(path).unwrap();
+        Self::open(path.into()).unwrap()
+    }
+
+    /// # Errors
+    ///
+    /// Returns an error if the image can't be read or decoded.
+    pub fn open(path: impl AsRef<Path>) -> image::ImageResult<Self> {
+        let image = image::open(path)?;
         let rgb = image.to_rgb8();
-        Self { image: rgb }
+        Ok(Self { image: rgb })
+    }
+}
+
+impl Fingerprint for ImageTexture {
+    fn fingerprint(&self, hasher: &mut dyn Hasher) {
+        "ImageTexture".fingerprint(hasher);
+        self.image.width().fingerprint(hasher);
+        self.image.height().fingerprint(hasher);
+        hasher.write(self.image.as_raw());
     }
 }
 
@@ -7,6 +7,9 @@ pub struct TimedRay {
     // Time that the ray was emitted, between 0 and 1
     // Not to be confused with parameterization of the ray through space.
     pub time: f32,
+    // Seeds the random choices of what the ray passes through, such as how far it gets
+    // into a volume, so that hit queries don't use up the sampler's dimensions
+    pub key: u64,
 }
 
 impl TimedRay {
@@ -15,9 +18,14 @@ impl TimedRay {
             origin,
             direction,
             time,
+            key: 0,
         }
     }
 
+    pub fn with_key(self, key: u64) -> Self {
+        Self { key, ..self }
+    }
+
     pub fn at(&self, t: f32) -> Vec3 {
         self.origin + self.direction * t
     }
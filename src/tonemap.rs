@@ -0,0 +1,176 @@
+//! Turning linear radiance into colors a display can show.
+
+use glam::{
+    Mat3A,
+    Vec3A as Vec3,
+};
+
+use crate::color::Color;
+
+/// How radiance above 1 is squeezed into the displayable range.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Operator {
+    /// Cuts off everything above 1.
+    Clamp,
+    /// `c / (1 + c)`, which never quite reaches white.
+    Reinhard,
+    /// Reinhard stretched so that `white` maps to exactly 1.
+    ExtendedReinhard { white: f32 },
+    /// Krzysztof Narkowicz's fit of the ACES filmic curve.
+    Aces,
+    /// A fit of Blender's `AgX`, which desaturates bright colors towards white rather
+    /// than skewing their hue.
+    Agx,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ToneMap {
+    /// Stops to brighten the image by before the operator is applied.
+    exposure: f32,
+    operator: Operator,
+}
+
+impl Default for ToneMap {
+    fn default() -> Self {
+        Self {
+            exposure: 0.0,
+            operator: Operator::Clamp,
+        }
+    }
+}
+
+impl ToneMap {
+    /// # Errors
+    ///
+    /// Returns an error if `exposure` isn't finite, or the extended Reinhard white
+    /// point isn't positive and finite.
+    pub fn new(exposure: f32, operator: Operator) -> Result<Self, String> {
+        if !exposure.is_finite() {
+            return Err(format!("exposure must be finite, found {exposure}"));
+        }
+        if let Operator::ExtendedReinhard { white } = operator {
+            if !(white.is_finite() && white > 0.0) {
+                return Err(format!("white point must be positive, found {white}"));
+            }
+        }
+        Ok(Self { exposure, operator })
+    }
+
+    /// Maps radiance to linear display values between 0 and 1.
+    pub fn apply(&self, color: Color) -> Color {
+        let c = (color.0 * self.exposure.exp2()).max(Vec3::ZERO);
+        let mapped = match self.operator {
+            Operator::Clamp => c,
+            Operator::Reinhard => c / (Vec3::ONE + c),
+            Operator::ExtendedReinhard { white } => {
+                c * (Vec3::ONE + c / (white * white)) / (Vec3::ONE + c)
+            }
+            Operator::Aces => aces(c),
+            Operator::Agx => agx(c),
+        };
+        Color(mapped.clamp(Vec3::ZERO, Vec3::ONE))
+    }
+}
+
+fn aces(c: Vec3) -> Vec3 {
+    // The fit is brighter than the reference curve, so the input is darkened to match
+    let c = c * 0.6;
+    (c * (c * 2.51 + 0.03)) / (c * (c * 2.43 + 0.59) + 0.14)
+}
+
+/// Benjamin Wrensch's minimal `AgX`: a log encoding with a sigmoid fitted to the
+/// default look, between matrices that keep highlights from clipping per channel.
+fn agx(c: Vec3) -> Vec3 {
+    const MIN_EV: f32 = -12.473_93;
+    const MAX_EV: f32 = 4.026_069;
+    let inset = Mat3A::from_cols_array(&[
+        0.842_479_06,
+        0.042_328_24,
+        0.042_375_655,
+        0.078_433_6,
+        0.878_468_6,
+        0.078_433_6,
+        0.079_223_745,
+        0.079_166_13,
+        0.879_143,
+    ]);
+    let outset = Mat3A::from_cols_array(&[
+        1.196_879,
+        -0.052_896_85,
+        -0.052_971_635,
+        -0.098_020_88,
+        1.151_903_1,
+        -0.098_043_45,
+        -0.099_029_74,
+        -0.098_961_18,
+        1.151_073_7,
+    ]);
+
+    let c = inset * c.max(Vec3::splat(f32::MIN_POSITIVE));
+    let x = Vec3::from_array(c.to_array().map(f32::log2))
+        .clamp(Vec3::splat(MIN_EV), Vec3::splat(MAX_EV));
+    let x = (x - MIN_EV) / (MAX_EV - MIN_EV);
+
+    let x2 = x * x;
+    let x4 = x2 * x2;
+    let curve = x4 * x2 * x * 15.5 - x4 * x2 * 40.14 + x4 * x * 31.96 - x4 * 6.868
+        + x2 * x * 0.4298
+        + x2 * 0.1191
+        - x * 0.002_32;
+
+    // The curve's output is display encoded with a 2.2 gamma
+    (outset * curve).max(Vec3::ZERO).powf(2.2)
+}
+
+/// The sRGB transfer function, from linear light to encoded values.
+pub fn srgb_oetf(linear: f32) -> f32 {
+    if linear <= 0.003_130_8 {
+        linear * 12.92
+    } else {
+        1.055 * linear.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_operators_stay_in_range_and_keep_order() {
+        let operators = [
+            Operator::Clamp,
+            Operator::Reinhard,
+            Operator::ExtendedReinhard { white: 4.0 },
+            Operator::Aces,
+            Operator::Agx,
+        ];
+        for operator in operators {
+            let tone_map = ToneMap::new(0.0, operator).unwrap();
+            let mut previous = -1.0;
+            for i in 0..=100 {
+                let value = i as f32 * 0.1;
+                let mapped = tone_map.apply(Color::new(value, value, value)).0;
+                assert!(mapped.min_element() >= 0.0 && mapped.max_element() <= 1.0);
+                assert!(mapped.x >= previous, "{operator:?} at {value}");
+                previous = mapped.x;
+            }
+        }
+
+        let white = ToneMap::new(0.0, Operator::ExtendedReinhard { white: 4.0 }).unwrap();
+        assert!((white.apply(Color::new(4.0, 4.0, 4.0)).0.x - 1.0).abs() < 1e-6);
+        let brighter = ToneMap::new(1.0, Operator::Clamp).unwrap();
+        assert!((brighter.apply(Color::new(0.25, 0.0, 0.0)).0.x - 0.5).abs() < 1e-6);
+
+        for white in [0.0, -1.0, f32::NAN, f32::INFINITY] {
+            assert!(ToneMap::new(0.0, Operator::ExtendedReinhard { white }).is_err());
+        }
+        assert!(ToneMap::new(f32::NAN, Operator::Clamp).is_err());
+    }
+
+    #[test]
+    fn test_srgb_oetf() {
+        assert!((srgb_oetf(1.0) - 1.0).abs() < 1e-6);
+        assert!((srgb_oetf(0.18) - 0.461_356).abs() < 1e-4);
+        assert_eq!(Color::new(0.5, 0.0, 1.0).bytes(), [188, 0, 255]);
+    }
+}